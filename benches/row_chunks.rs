@@ -0,0 +1,57 @@
+//! Throughput of moving query rows from the worker thread to the async side,
+//! one channel message per row versus chunks of `row_chunk_size` rows.
+//!
+//! Runs without a database: the rows go through the driver's own chunking and
+//! unchunking, with a shared column list and one text value per column.
+//!
+//! ```sh
+//! cargo bench --bench row_chunks
+//! ```
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use oracle::sql_type::OracleType;
+use rbdc_oracle::connection::transfer_rows;
+use rbdc_oracle::{OracleTypeInfo, OracleValue};
+
+const ROWS: usize = 20_000;
+const COLUMNS: usize = 20;
+/// The default `row_channel_size`.
+const CHANNEL_SIZE: usize = 50;
+
+fn table() -> (Vec<String>, Vec<Vec<OracleValue>>) {
+    let columns = (0..COLUMNS).map(|c| format!("column_{c}")).collect();
+    let type_info = OracleTypeInfo::from_oracle_type(OracleType::Varchar2(40));
+    let rows = (0..ROWS)
+        .map(|r| {
+            (0..COLUMNS)
+                .map(|c| OracleValue::new(Some(format!("{r}:{c}")), None, type_info.clone(), false))
+                .collect()
+        })
+        .collect();
+    (columns, rows)
+}
+
+fn row_chunks(c: &mut Criterion) {
+    let (columns, rows) = table();
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let mut group = c.benchmark_group("row_chunks");
+    group.throughput(Throughput::Elements(ROWS as u64));
+    for chunk_size in [1, 8, 32, 128] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chunk_size),
+            &chunk_size,
+            |b, &chunk_size| {
+                b.iter(|| {
+                    assert_eq!(
+                        transfer_rows(&columns, &rows, chunk_size, CHANNEL_SIZE),
+                        ROWS
+                    )
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, row_chunks);
+criterion_main!(benches);
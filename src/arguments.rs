@@ -1,5 +1,6 @@
 use crate::encode::{Encode, IsNull};
 use oracle::Statement;
+use oracle::sql_type::OracleType;
 use rbdc::Error;
 use rbs::Value;
 use std::str::FromStr;
@@ -135,8 +136,10 @@ impl OracleArgumentValue {
                 let value = bigdecimal::BigDecimal::from_str(value)
                     .map_err(|e| Error::from(e.to_string()))?
                     .to_string();
+                // bound as NUMBER, which the client parses with a `.` whatever
+                // the session's NLS_NUMERIC_CHARACTERS
                 statement
-                    .bind(index, &value)
+                    .bind(index, &(&value, &OracleType::Number(0, -127)))
                     .map_err(|e| Error::from(e.to_string()))?;
             }
             OracleArgumentValue::Timestamp(value) => statement
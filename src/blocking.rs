@@ -0,0 +1,389 @@
+//! Synchronous connections for code without an async runtime.
+//!
+//! A [`blocking::OracleConnection`](OracleConnection) runs its statements on
+//! the calling thread, with the same statement cache, transaction handling and
+//! type mapping as the async [`crate::OracleConnection`].
+//!
+//! ```no_run
+//! use rbdc_oracle::blocking::OracleConnection;
+//! use rbdc_oracle::OracleConnectOptions;
+//! use rbdc::db::Row;
+//! use rbs::Value;
+//!
+//! # fn main() -> Result<(), rbdc::Error> {
+//! let options = OracleConnectOptions::new()
+//!     .username("scott")
+//!     .password("tiger")
+//!     .connect_string("//localhost:1521/XE");
+//! let mut conn = OracleConnection::connect(&options)?;
+//! conn.transaction(|conn| {
+//!     conn.execute("INSERT INTO t (id) VALUES (?)", vec![Value::I32(1)])?;
+//!     Ok(())
+//! })?;
+//! let mut row = conn.query_row("SELECT COUNT(*) FROM t", vec![])?;
+//! let count: Value = row.get(0)?;
+//! conn.close()
+//! # }
+//! ```
+
+use crate::connection::ConnectionState;
+use crate::connection::establish::{EstablishParams, with_options};
+use crate::connection::execute;
+use crate::connection::worker::{end_dropped, end_transaction};
+use crate::options::CloseBehavior;
+use crate::{OracleArguments, OracleConnectOptions, OracleQueryResult, OracleRow};
+use either::Either;
+use futures_util::future;
+use rbdc::Error;
+use rbs::Value;
+use std::fmt::{self, Debug, Formatter};
+
+/// A connection whose methods block the calling thread.
+///
+/// `connect_timeout` does not apply; use the `connect_timeout` of the Easy
+/// Connect string or `sqlnet.ora` instead. Credential and token providers are
+/// awaited on the calling thread, so they must not need a particular runtime.
+pub struct OracleConnection {
+    /// `None` once closed.
+    state: Option<Box<dyn BlockingBackend>>,
+    close_behavior: CloseBehavior,
+}
+
+/// What a blocking connection runs its statements on; a session in
+/// production, a fake in tests.
+pub(crate) trait BlockingBackend: Send {
+    /// Feeds the results of `sql` to `f` until it returns `false`, fetching
+    /// at most `row_limit` rows of each statement.
+    fn run(
+        &mut self,
+        sql: &str,
+        arguments: Option<OracleArguments>,
+        persistent: bool,
+        row_limit: Option<u32>,
+        f: &mut dyn FnMut(Either<OracleQueryResult, OracleRow>) -> bool,
+    ) -> Result<(), Error>;
+
+    fn transaction_active(&self) -> bool;
+
+    fn ping(&mut self) -> Result<(), Error>;
+
+    /// Ends an open transaction before closing, see [`end_transaction`].
+    fn end_transaction(&mut self, behavior: CloseBehavior) -> Result<(), Error>;
+
+    /// The connection was dropped without `close`, see [`end_dropped`].
+    fn end_dropped(&mut self, behavior: CloseBehavior);
+
+    /// Logs off.
+    fn close(&mut self) -> Result<(), Error>;
+}
+
+impl BlockingBackend for ConnectionState {
+    fn run(
+        &mut self,
+        sql: &str,
+        arguments: Option<OracleArguments>,
+        persistent: bool,
+        row_limit: Option<u32>,
+        f: &mut dyn FnMut(Either<OracleQueryResult, OracleRow>) -> bool,
+    ) -> Result<(), Error> {
+        for step in execute::iter(self, sql, arguments, persistent, row_limit)? {
+            if !f(step?) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn transaction_active(&self) -> bool {
+        self.transaction_active
+    }
+
+    fn ping(&mut self) -> Result<(), Error> {
+        self.handle
+            .connection()
+            .ping()
+            .map_err(|e| Error::from(e.to_string()))
+    }
+
+    fn end_transaction(&mut self, behavior: CloseBehavior) -> Result<(), Error> {
+        end_transaction(self, behavior)
+    }
+
+    fn end_dropped(&mut self, behavior: CloseBehavior) {
+        end_dropped(self, behavior)
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        self.handle
+            .connection()
+            .close()
+            .map_err(|e| Error::from(e.to_string()))
+    }
+}
+
+impl OracleConnection {
+    /// Logs on with the retries, failover hosts and providers of `options`.
+    pub fn connect(options: &OracleConnectOptions) -> Result<Self, Error> {
+        Self::connect_with(options, EstablishParams::establish)
+    }
+
+    /// `connect` with `start` opening the session of each attempt.
+    pub(crate) fn connect_with<B: BlockingBackend + 'static>(
+        options: &OracleConnectOptions,
+        start: fn(&EstablishParams) -> Result<B, Error>,
+    ) -> Result<Self, Error> {
+        let state = futures_executor::block_on(with_options(options, |params| {
+            future::ready(start(&params))
+        }))?;
+        Ok(Self {
+            state: Some(Box::new(state)),
+            close_behavior: options.close_behavior,
+        })
+    }
+
+    /// Runs `sql`, which may hold several statements, and returns the rows
+    /// affected by all of them. `?` placeholders are bound to `params`.
+    pub fn execute(&mut self, sql: &str, params: Vec<Value>) -> Result<OracleQueryResult, Error> {
+        let mut result = OracleQueryResult::default();
+        self.run(sql, params, None, |step| {
+            if let Either::Left(step) = step {
+                result.extend([step]);
+            }
+            true
+        })?;
+        Ok(result)
+    }
+
+    /// All rows returned by `sql`.
+    pub fn query(&mut self, sql: &str, params: Vec<Value>) -> Result<Vec<OracleRow>, Error> {
+        let mut rows = Vec::new();
+        self.run(sql, params, None, |step| {
+            if let Either::Right(row) = step {
+                rows.push(row);
+            }
+            true
+        })?;
+        Ok(rows)
+    }
+
+    /// The first row returned by `sql`; an error when there is none. Only
+    /// that row is fetched from the server.
+    pub fn query_row(&mut self, sql: &str, params: Vec<Value>) -> Result<OracleRow, Error> {
+        let mut first = None;
+        self.run(sql, params, Some(1), |step| match step {
+            Either::Right(row) => {
+                first = Some(row);
+                false
+            }
+            Either::Left(_) => true,
+        })?;
+        first.ok_or_else(|| Error::from("query returned no rows"))
+    }
+
+    /// Starts a transaction; statements are no longer committed one by one.
+    pub fn begin(&mut self) -> Result<(), Error> {
+        self.execute("begin", vec![]).map(|_| ())
+    }
+
+    pub fn commit(&mut self) -> Result<(), Error> {
+        self.execute("commit", vec![]).map(|_| ())
+    }
+
+    pub fn rollback(&mut self) -> Result<(), Error> {
+        self.execute("rollback", vec![]).map(|_| ())
+    }
+
+    /// Runs `f` in a transaction that is committed when it returns `Ok` and
+    /// rolled back when it returns an error.
+    pub fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.begin()?;
+        match f(self) {
+            Ok(value) => {
+                self.commit()?;
+                Ok(value)
+            }
+            Err(e) => {
+                if let Err(rollback) = self.rollback() {
+                    log::warn!("rolling back after `{e}` failed: {rollback}");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    pub fn transaction_active(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_some_and(|state| state.transaction_active())
+    }
+
+    pub fn ping(&mut self) -> Result<(), Error> {
+        self.state()?.ping()
+    }
+
+    /// Ends an open transaction according to `close_behavior` and logs off.
+    /// With `CloseBehavior::Error` the connection stays open when a
+    /// transaction is still active.
+    pub fn close(&mut self) -> Result<(), Error> {
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+        state.end_transaction(self.close_behavior)?;
+        let mut state = self.state.take().expect("checked above");
+        state.close()
+    }
+
+    fn state(&mut self) -> Result<&mut Box<dyn BlockingBackend>, Error> {
+        self.state
+            .as_mut()
+            .ok_or_else(|| Error::from("connection is closed"))
+    }
+
+    /// Feeds the results of `sql` to `f` until it returns `false`, fetching
+    /// at most `row_limit` rows of each statement.
+    fn run(
+        &mut self,
+        sql: &str,
+        params: Vec<Value>,
+        row_limit: Option<u32>,
+        mut f: impl FnMut(Either<OracleQueryResult, OracleRow>) -> bool,
+    ) -> Result<(), Error> {
+        let sql = crate::OracleDriver.pub_exchange(sql);
+        let persistent = !params.is_empty();
+        let arguments = if persistent {
+            Some(OracleArguments::from_args(params)?)
+        } else {
+            None
+        };
+        self.state()?
+            .run(&sql, arguments, persistent, row_limit, &mut f)
+    }
+}
+
+impl Debug for OracleConnection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OracleConnection")
+            .field("closed", &self.state.is_none())
+            .field("transaction_active", &self.transaction_active())
+            .finish()
+    }
+}
+
+impl Drop for OracleConnection {
+    fn drop(&mut self) {
+        if let Some(state) = &mut self.state {
+            state.end_dropped(self.close_behavior);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OracleConnection;
+    use crate::OracleConnectOptions;
+    use crate::connection::establish::EstablishParams;
+    use crate::connection::fake::{COMMITTED, FakeBackend, ROW_LIMITS};
+    use crate::options::CloseBehavior;
+    use rbdc::Error;
+
+    fn start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+        Ok(FakeBackend::new(params))
+    }
+
+    fn connect(options: &OracleConnectOptions) -> OracleConnection {
+        OracleConnection::connect_with(options, start).expect("connect")
+    }
+
+    fn row_limit(query: &str) -> Option<u32> {
+        ROW_LIMITS
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(executed, _)| executed == query)
+            .and_then(|(_, row_limit)| *row_limit)
+    }
+
+    fn committed(query: &str) -> bool {
+        COMMITTED
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|committed| committed == query)
+    }
+
+    #[test]
+    fn test_query_row_fetches_one_row() {
+        let mut conn = connect(&OracleConnectOptions::new());
+        let row = conn
+            .query_row("SELECT n FROM numbers WHERE n <= 7", vec![])
+            .expect("query_row");
+        assert_eq!(row.try_get::<String>(0).expect("decode"), "1");
+        assert_eq!(row_limit("SELECT n FROM numbers WHERE n <= 7"), Some(1));
+
+        let error = conn
+            .query_row("SELECT n FROM numbers WHERE n <= 0", vec![])
+            .expect_err("no rows");
+        assert!(error.to_string().contains("no rows"), "{error}");
+
+        let rows = conn
+            .query("SELECT n FROM numbers WHERE n <= 6", vec![])
+            .expect("query");
+        assert_eq!(rows.len(), 6);
+        assert_eq!(row_limit("SELECT n FROM numbers WHERE n <= 6"), None);
+        conn.close().expect("close");
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_error() {
+        let mut conn = connect(&OracleConnectOptions::new());
+        let error = conn
+            .transaction(|conn| {
+                conn.execute("SELECT n FROM numbers WHERE n <= 11", vec![])?;
+                assert!(conn.transaction_active());
+                Err::<(), _>(Error::from("import failed"))
+            })
+            .expect_err("closure failed");
+        assert_eq!(error.to_string(), "import failed");
+        assert!(!conn.transaction_active());
+        assert!(!committed("SELECT n FROM numbers WHERE n <= 11"));
+
+        conn.transaction(|conn| conn.execute("SELECT n FROM numbers WHERE n <= 12", vec![]))
+            .expect("transaction");
+        assert!(!conn.transaction_active());
+        assert!(committed("SELECT n FROM numbers WHERE n <= 12"));
+        conn.close().expect("close");
+    }
+
+    #[test]
+    fn test_close_refused_with_open_transaction() {
+        let options = OracleConnectOptions::new().close_behavior(CloseBehavior::Error);
+        let mut conn = connect(&options);
+        conn.begin().expect("begin");
+        conn.execute("SELECT n FROM numbers WHERE n <= 13", vec![])
+            .expect("execute");
+        let error = conn.close().expect_err("open transaction");
+        assert!(error.to_string().contains("open transaction"), "{error}");
+        // still open, so the caller can decide
+        assert!(conn.transaction_active());
+        conn.ping().expect("ping");
+
+        conn.rollback().expect("rollback");
+        conn.close().expect("close");
+        assert!(!committed("SELECT n FROM numbers WHERE n <= 13"));
+        let error = conn.ping().expect_err("closed");
+        assert!(error.to_string().contains("closed"), "{error}");
+    }
+
+    #[test]
+    fn test_invalid_options_fail_before_logon() {
+        let options = OracleConnectOptions::new()
+            .connect_string("PROD")
+            .add_host("standby");
+        let error = OracleConnection::connect(&options).expect_err("invalid hosts");
+        assert!(error.to_string().contains("Easy Connect"), "{error}");
+    }
+}
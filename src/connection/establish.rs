@@ -1,40 +1,223 @@
 use crate::OracleConnectOptions;
-use crate::connection::{ConnectionHandle, ConnectionState, Statements};
-use oracle::Connection as RawOracleConnection;
+use crate::connection::threads::ThreadSettings;
+use crate::connection::timer;
+use crate::connection::{ConnectionHandle, ConnectionState, SessionAttribute, Statements};
+use crate::error::has_error_code;
+use crate::options::OracleProtocol;
+use crate::options::{
+    AccessToken, CloseBehavior, ConnectRetry, CredentialProvider, Credentials, SecretString,
+    TcpsDescriptor, TokenProvider,
+};
+use futures_core::future::BoxFuture;
+use oracle::{Connector, InitParams};
 use rbdc::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 static THREAD_ID: AtomicU64 = AtomicU64::new(0);
+static TOKEN_DIR_ID: AtomicU64 = AtomicU64::new(0);
+static CLIENT_CONFIG_DIR: OnceLock<String> = OnceLock::new();
+
+/// Rejected credentials: invalid username/password and expired password.
+const CREDENTIAL_ERRORS: &[&str] = &["ORA-01017", "ORA-28001"];
+
+const PASSWORD_EXPIRED: &str = "ORA-28001";
+
+/// Rejected tokens: invalid login, invalid token and expired token.
+const TOKEN_ERRORS: &[&str] = &["ORA-01017", "ORA-25707", "ORA-25708"];
+
+/// Listener and instance errors that usually go away on their own: no
+/// listener, unknown service or SID, no handler available, instance blocked or
+/// starting up, and network timeouts.
+const TRANSIENT_ERRORS: &[&str] = &[
+    "ORA-12170",
+    "ORA-12505",
+    "ORA-12514",
+    "ORA-12516",
+    "ORA-12519",
+    "ORA-12520",
+    "ORA-12521",
+    "ORA-12528",
+    "ORA-12537",
+    "ORA-12541",
+    "ORA-12543",
+    "ORA-01033",
+    "ORA-01034",
+    "ORA-01089",
+];
+
+/// Prefix of the error returned when an attempt exceeds `connect_timeout`.
+pub(crate) const CONNECT_TIMEOUT: &str = "connect timed out";
 
 #[derive(Clone, Debug)]
 pub(crate) struct EstablishParams {
     username: String,
-    password: String,
-    connect_string: String,
+    pub(crate) password: SecretString,
+    pub(crate) new_password: Option<SecretString>,
+    target: Target,
+    /// Failover addresses tried after `target`, see [`with_failover`].
+    alternates: Vec<Target>,
+    access_token: Option<AccessToken>,
     statement_cache_capacity: usize,
+    config_dir: Option<String>,
+    pub(crate) edition: Option<String>,
+    driver_name: String,
+    pub(crate) session_attributes: Vec<(SessionAttribute, String)>,
+    session_statements: Vec<String>,
     pub(crate) thread_name: String,
+    pub(crate) threads: ThreadSettings,
     pub(crate) command_channel_size: usize,
+    pub(crate) row_chunk_size: usize,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) close_behavior: CloseBehavior,
+}
+
+/// One listener address to connect to.
+#[derive(Clone, Debug)]
+struct Target {
+    host: String,
+    connect_string: String,
+    token_descriptor: Option<TcpsDescriptor>,
 }
 
 impl EstablishParams {
     pub(crate) fn from_options(options: &OracleConnectOptions) -> Result<Self, Error> {
+        let token_auth = options.access_token.is_some() || options.token_provider.is_some();
+        if token_auth && options.credential_provider.is_some() {
+            return Err(Error::from(
+                "credential_provider cannot be combined with token authentication",
+            ));
+        }
+        if token_auth && options.protocol != OracleProtocol::Tcps {
+            return Err(Error::from("token authentication requires protocol tcps"));
+        }
+        let mut targets = options
+            .failover_targets()?
+            .into_iter()
+            .map(|(host, options)| {
+                Ok(Target {
+                    host,
+                    connect_string: options.effective_connect_string()?,
+                    token_descriptor: token_auth.then(|| options.tcps_descriptor()).transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let target = targets.remove(0);
+
+        let id = THREAD_ID.fetch_add(1, Ordering::AcqRel);
         Ok(Self {
             username: options.username.clone(),
             password: options.password.clone(),
-            connect_string: options.connect_string.clone(),
+            new_password: options.new_password.clone(),
+            target,
+            alternates: targets,
+            access_token: options.access_token.clone(),
             statement_cache_capacity: options.statement_cache_capacity,
-            thread_name: format!(
-                "rbdc-oracle-worker-{}",
-                THREAD_ID.fetch_add(1, Ordering::AcqRel)
-            ),
+            config_dir: options.config_dir.clone(),
+            edition: options.edition.clone(),
+            driver_name: options.driver_name.clone(),
+            session_attributes: session_attributes(options),
+            session_statements: options.session_statements(),
+            thread_name: format!("{}-{id}", options.worker_thread_name),
+            threads: ThreadSettings {
+                shared_threads: options.worker_threads,
+                stack_size: options.worker_stack_size,
+                name_prefix: options.worker_thread_name.clone(),
+            },
             command_channel_size: options.command_channel_size,
+            row_chunk_size: options.row_chunk_size.max(1),
+            connect_timeout: options.connect_timeout,
+            close_behavior: options.close_behavior,
         })
     }
 
+    /// The address these params connect to, `host:port` for Easy Connect.
+    pub(crate) fn host(&self) -> &str {
+        &self.target.host
+    }
+
+    pub(crate) fn set_credentials(&mut self, credentials: Credentials) {
+        self.username = credentials.username;
+        self.password = credentials.password;
+    }
+
+    pub(crate) fn set_access_token(&mut self, token: AccessToken) {
+        self.access_token = Some(token);
+    }
+
+    /// The descriptor pointing the client at the token files in `token_dir`.
+    fn token_connect_string(&self, token: &AccessToken, token_dir: &Path) -> Result<String, Error> {
+        let descriptor = self
+            .target
+            .token_descriptor
+            .as_ref()
+            .ok_or_else(|| Error::from("token authentication requires protocol tcps"))?;
+        Ok(descriptor.to_connect_string(&format!(
+            "(TOKEN_AUTH={})(TOKEN_LOCATION=\"{}\")",
+            token.token_auth(),
+            token_dir.display()
+        )))
+    }
+
     pub(crate) fn establish(&self) -> Result<ConnectionState, Error> {
-        let connection =
-            RawOracleConnection::connect(&self.username, &self.password, &self.connect_string)
-                .map_err(|e| Error::from(e.to_string()))?;
+        if let Some(config_dir) = &self.config_dir {
+            init_client(config_dir)?;
+        }
+
+        // The Oracle client reads the token from disk during logon only, so the
+        // files live just as long as the connect call. Each attempt gets its own
+        // directory: one abandoned by `connect_timeout` may still be running.
+        let (mut connector, token_dir) = match &self.access_token {
+            Some(token) => {
+                let token_dir = TokenDir::write(token)?;
+                let connect_string = self.token_connect_string(token, &token_dir.0)?;
+                let mut connector = Connector::new("", "", connect_string);
+                connector.external_auth(true);
+                (connector, Some(token_dir))
+            }
+            None => (
+                Connector::new(
+                    &self.username,
+                    self.password.expose_secret(),
+                    &self.target.connect_string,
+                ),
+                None,
+            ),
+        };
+        connector.driver_name(&self.driver_name);
+        if let Some(new_password) = &self.new_password {
+            connector.new_password(new_password.expose_secret());
+        }
+        if let Some(edition) = &self.edition {
+            connector.edition(edition);
+        }
+        let connection = connector.connect().map_err(|e| {
+            if self.new_password.is_none() && e.to_string().contains(PASSWORD_EXPIRED) {
+                Error::from(format!(
+                    "{e} (set a new one with OracleConnectOptions::connect_with_new_password)"
+                ))
+            } else {
+                Error::from(e.to_string())
+            }
+        })?;
+        drop(token_dir);
+
+        for (attribute, value) in &self.session_attributes {
+            if let Err(e) = attribute.apply(&connection, value) {
+                let _ = connection.close();
+                return Err(e);
+            }
+        }
+
+        for sql in &self.session_statements {
+            if let Err(e) = connection.execute(sql, &[]) {
+                let _ = connection.close();
+                return Err(Error::from(format!("session setup `{sql}` failed: {e}")));
+            }
+        }
 
         Ok(ConnectionState {
             handle: ConnectionHandle::new(connection),
@@ -43,3 +226,503 @@ impl EstablishParams {
         })
     }
 }
+
+/// Runs `connect` for `options`, with the configured credential or token
+/// provider, retries and failover.
+pub(crate) async fn with_options<T, F, Fut>(
+    options: &OracleConnectOptions,
+    connect: F,
+) -> Result<T, Error>
+where
+    F: Fn(EstablishParams) -> Fut + Copy,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let params = EstablishParams::from_options(options)?;
+    let retry = options.connect_retry;
+    let attempt =
+        move |params| with_retry(retry, params, move |params| with_failover(params, connect));
+    if let Some(provider) = &options.token_provider {
+        with_token_provider(provider.provider(), params, attempt).await
+    } else if let Some(provider) = &options.credential_provider {
+        with_credential_provider(provider.provider(), params, attempt).await
+    } else {
+        attempt(params).await
+    }
+}
+
+/// Runs `attempt` with credentials from `provider`, refreshing them once if the
+/// server rejects them.
+pub(crate) async fn with_credential_provider<T, F, Fut>(
+    provider: &dyn CredentialProvider,
+    params: EstablishParams,
+    attempt: F,
+) -> Result<T, Error>
+where
+    F: FnMut(EstablishParams) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    with_refreshed_secret(
+        params,
+        CREDENTIAL_ERRORS,
+        || provider.credentials(),
+        || provider.invalidate(),
+        EstablishParams::set_credentials,
+        attempt,
+    )
+    .await
+}
+
+/// Runs `attempt` with a token from `provider`, fetching a new one once if the
+/// server rejects it.
+pub(crate) async fn with_token_provider<T, F, Fut>(
+    provider: &dyn TokenProvider,
+    params: EstablishParams,
+    attempt: F,
+) -> Result<T, Error>
+where
+    F: FnMut(EstablishParams) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    with_refreshed_secret(
+        params,
+        TOKEN_ERRORS,
+        || provider.access_token(),
+        || provider.invalidate(),
+        EstablishParams::set_access_token,
+        attempt,
+    )
+    .await
+}
+
+async fn with_refreshed_secret<'a, S, T, F, Fut>(
+    mut params: EstablishParams,
+    rejected: &[&str],
+    fetch: impl Fn() -> BoxFuture<'a, Result<S, Error>>,
+    invalidate: impl Fn(),
+    apply: fn(&mut EstablishParams, S),
+    mut attempt: F,
+) -> Result<T, Error>
+where
+    F: FnMut(EstablishParams) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    apply(&mut params, fetch().await?);
+    match attempt(params.clone()).await {
+        Err(e) if has_error_code(&e, rejected) => {
+            log::warn!("login rejected, refreshing from the provider: {e}");
+            invalidate();
+            apply(&mut params, fetch().await?);
+            attempt(params).await
+        }
+        result => result,
+    }
+}
+
+/// Runs `attempt` up to `retry.attempts` times, backing off between attempts
+/// that failed with a transient error or timed out.
+pub(crate) async fn with_retry<T, F, Fut>(
+    retry: ConnectRetry,
+    params: EstablishParams,
+    mut attempt: F,
+) -> Result<T, Error>
+where
+    F: FnMut(EstablishParams) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut retries = 0;
+    loop {
+        match attempt(params.clone()).await {
+            Err(e) if retries + 1 < retry.attempts && is_transient(&e) => {
+                retries += 1;
+                let delay = retry.jittered_delay(retries);
+                log::warn!(
+                    "connect attempt {retries}/{} failed, retrying in {delay:?}: {e}",
+                    retry.attempts
+                );
+                timer::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs `attempt` against the primary address, then against every failover
+/// address in turn while attempts fail with a transient error or time out.
+pub(crate) async fn with_failover<T, F, Fut>(
+    mut params: EstablishParams,
+    mut attempt: F,
+) -> Result<T, Error>
+where
+    F: FnMut(EstablishParams) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut alternates = std::mem::take(&mut params.alternates).into_iter();
+    loop {
+        let host = params.target.host.clone();
+        match attempt(params.clone()).await {
+            Err(e) if is_transient(&e) && alternates.len() > 0 => {
+                let next = alternates.next().expect("alternate");
+                log::warn!(
+                    "connect to {host} failed, failing over to {}: {e}",
+                    next.host
+                );
+                params.target = next;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_transient(e: &Error) -> bool {
+    has_error_code(e, TRANSIENT_ERRORS) || e.to_string().starts_with(CONNECT_TIMEOUT)
+}
+
+/// Private directory holding the token (and IAM private key) for one logon,
+/// removed on drop.
+struct TokenDir(PathBuf);
+
+impl TokenDir {
+    /// Writes `token` into a new directory with an unpredictable name. An
+    /// existing path is never reused, so nobody can plant one in its place.
+    fn write(token: &AccessToken) -> Result<Self, Error> {
+        let dir = std::env::temp_dir().join(format!(
+            "rbdc-oracle-token-{}-{}-{:016x}",
+            std::process::id(),
+            TOKEN_DIR_ID.fetch_add(1, Ordering::Relaxed),
+            fastrand::u64(..)
+        ));
+        let to_error = |e: std::io::Error| Error::from(format!("{}: {e}", dir.display()));
+
+        create_private_dir(&dir).map_err(to_error)?;
+        let guard = Self(dir.clone());
+
+        let mut files = vec![("token", &token.token)];
+        if let Some(private_key) = &token.private_key {
+            files.push(("oci_db_key.pem", private_key));
+        }
+        for (name, secret) in files {
+            let path = dir.join(name);
+            fs::write(&path, secret.expose_secret()).map_err(to_error)?;
+            restrict_permissions(&path, 0o600).map_err(to_error)?;
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for TokenDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Creates `dir`, failing when it already exists, readable by the owner only.
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir(dir)
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn init_client(config_dir: &str) -> Result<(), Error> {
+    let initialized = InitParams::new()
+        .oracle_client_config_dir(config_dir)
+        .and_then(|params| params.init())
+        .map_err(|e| Error::from(e.to_string()))?;
+
+    if initialized {
+        CLIENT_CONFIG_DIR.set(config_dir.to_owned()).ok();
+    } else if CLIENT_CONFIG_DIR.get().map(String::as_str) != Some(config_dir) {
+        log::warn!("Oracle client already initialized, config_dir `{config_dir}` is ignored");
+    }
+    Ok(())
+}
+
+fn session_attributes(options: &OracleConnectOptions) -> Vec<(SessionAttribute, String)> {
+    [
+        (SessionAttribute::Module, &options.module),
+        (SessionAttribute::Action, &options.action),
+        (
+            SessionAttribute::ClientIdentifier,
+            &options.client_identifier,
+        ),
+        (SessionAttribute::ClientInfo, &options.client_info),
+    ]
+    .into_iter()
+    .filter_map(|(attribute, value)| value.clone().map(|value| (attribute, value)))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CONNECT_TIMEOUT, EstablishParams, TokenDir, create_private_dir, with_credential_provider,
+        with_failover, with_retry, with_token_provider,
+    };
+    use crate::OracleConnectOptions;
+    use crate::options::{
+        AccessToken, ConnectRetry, CredentialProvider, Credentials, OracleProtocol, TokenProvider,
+    };
+    use futures_core::future::BoxFuture;
+    use rbdc::Error;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Rotating {
+        version: AtomicUsize,
+        invalidated: AtomicUsize,
+    }
+
+    impl CredentialProvider for Rotating {
+        fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+            let version = self.version.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(Credentials::new("app", format!("secret-{version}"))) })
+        }
+
+        fn invalidate(&self) {
+            self.invalidated.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn params() -> EstablishParams {
+        EstablishParams::from_options(&OracleConnectOptions::new()).expect("params")
+    }
+
+    #[tokio::test]
+    async fn test_refresh_after_invalid_login() {
+        let provider = Rotating::default();
+        let result = with_credential_provider(&provider, params(), |params| async move {
+            match params.password.expose_secret() {
+                "secret-0" => Err(Error::from("ORA-01017: invalid username/password")),
+                password => Ok(password.to_owned()),
+            }
+        })
+        .await;
+
+        assert_eq!(result.expect("retried"), "secret-1");
+        assert_eq!(provider.invalidated.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_only_once() {
+        let provider = Rotating::default();
+        let result: Result<(), Error> =
+            with_credential_provider(&provider, params(), |_| async move {
+                Err(Error::from("ORA-28001: the password has expired"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(provider.version.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_not_retried() {
+        let provider = Rotating::default();
+        let result: Result<(), Error> =
+            with_credential_provider(&provider, params(), |_| async move {
+                Err(Error::from("ORA-12541: TNS:no listener"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(provider.version.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.invalidated.load(Ordering::SeqCst), 0);
+    }
+
+    fn fast_retry(attempts: u32) -> ConnectRetry {
+        ConnectRetry::new(attempts).backoff(Duration::from_millis(1), Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        let calls = AtomicUsize::new(0);
+        let result = with_retry(fast_retry(3), params(), |_| {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match call {
+                    0 => Err(Error::from("ORA-12541: TNS:no listener")),
+                    1 => Err(Error::from(format!("{CONNECT_TIMEOUT} after 1s"))),
+                    _ => Ok(call),
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.expect("retried"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let calls = AtomicUsize::new(0);
+        let result: Result<(), Error> = with_retry(fast_retry(3), params(), |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async {
+                Err(Error::from(
+                    "ORA-12514: TNS:listener does not currently know of service",
+                ))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_permanent_errors() {
+        let calls = AtomicUsize::new(0);
+        let result: Result<(), Error> = with_retry(fast_retry(3), params(), |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::from("ORA-01017: invalid username/password")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    fn failover_params() -> EstablishParams {
+        EstablishParams::from_options(
+            &OracleConnectOptions::new()
+                .connect_string("//primary/svc")
+                .add_host("standby:1522"),
+        )
+        .expect("params")
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_next_host() {
+        let result = with_failover(failover_params(), |params| async move {
+            match params.host() {
+                "primary:1521" => Err(Error::from("ORA-12541: TNS:no listener")),
+                host => Ok(host.to_owned()),
+            }
+        })
+        .await;
+
+        assert_eq!(result.expect("failed over"), "standby:1522");
+    }
+
+    #[tokio::test]
+    async fn test_failover_stops_on_permanent_errors() {
+        let calls = AtomicUsize::new(0);
+        let result: Result<(), Error> = with_failover(failover_params(), |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::from("ORA-01017: invalid username/password")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    struct StubTokens {
+        issued: AtomicUsize,
+    }
+
+    impl TokenProvider for StubTokens {
+        fn access_token(&self) -> BoxFuture<'_, Result<AccessToken, Error>> {
+            let issued = self.issued.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(AccessToken::oauth(format!("token-{issued}"))) })
+        }
+    }
+
+    fn token_options() -> OracleConnectOptions {
+        OracleConnectOptions::new()
+            .connect_string("//db.example.com:2484/svc")
+            .protocol(OracleProtocol::Tcps)
+    }
+
+    #[tokio::test]
+    async fn test_refresh_expired_token() {
+        let provider = Arc::new(StubTokens {
+            issued: AtomicUsize::new(0),
+        });
+        let options = token_options().token_provider(Arc::clone(&provider));
+        let params = EstablishParams::from_options(&options).expect("params");
+        let result = with_token_provider(provider.as_ref(), params, |params| async move {
+            let token = params.access_token.clone().expect("token");
+            match token.token.expose_secret() {
+                "token-0" => Err(Error::from("ORA-25708: token has expired")),
+                _ => Ok(params
+                    .token_connect_string(&token, Path::new("/tmp/tokens"))
+                    .expect("descriptor")),
+            }
+        })
+        .await
+        .expect("retried");
+
+        assert!(result.starts_with(
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=db.example.com)(PORT=2484))\
+             (CONNECT_DATA=(SERVICE_NAME=svc))(SECURITY=(TOKEN_AUTH=OAUTH)(TOKEN_LOCATION=\""
+        ));
+        assert_eq!(provider.issued.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_token_auth_requires_tcps() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//h/svc")
+            .access_token(AccessToken::oauth("t"));
+        assert!(EstablishParams::from_options(&options).is_err());
+
+        let options = token_options()
+            .access_token(AccessToken::oauth("t"))
+            .credential_provider(Rotating::default());
+        assert!(EstablishParams::from_options(&options).is_err());
+    }
+
+    #[test]
+    fn test_token_files() {
+        let params = EstablishParams::from_options(
+            &token_options().access_token(AccessToken::iam("t", "key")),
+        )
+        .expect("params");
+        let token = params.access_token.clone().expect("token");
+        let dir = TokenDir::write(&token).expect("token dir");
+        let connect_string = params
+            .token_connect_string(&token, &dir.0)
+            .expect("descriptor");
+        assert!(connect_string.contains("(TOKEN_AUTH=OCI_TOKEN)"));
+        assert!(connect_string.contains(&*dir.0.to_string_lossy()));
+        assert_eq!(
+            std::fs::read_to_string(dir.0.join("token")).expect("token"),
+            "t"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.0.join("oci_db_key.pem")).expect("key"),
+            "key"
+        );
+
+        // A retry running next to an abandoned attempt writes elsewhere, and
+        // the abandoned attempt's cleanup leaves it alone.
+        let retry = TokenDir::write(&token).expect("token dir");
+        assert_ne!(dir.0, retry.0);
+        let path = dir.0.clone();
+        drop(dir);
+        assert!(!path.exists());
+        assert!(retry.0.join("token").exists());
+        assert!(
+            create_private_dir(&retry.0).is_err(),
+            "existing dirs are not reused"
+        );
+    }
+}
@@ -11,6 +11,7 @@ pub struct ExecuteIter<'a> {
     statement: &'a mut crate::statement::VirtualStatement,
     args: Option<OracleArguments>,
     args_used: usize,
+    row_limit: Option<u32>,
     goto_next: bool,
     finished: bool,
     buffered: VecDeque<Result<Either<OracleQueryResult, OracleRow>, Error>>,
@@ -21,6 +22,7 @@ pub(crate) fn iter<'a>(
     query: &'a str,
     args: Option<OracleArguments>,
     persistent: bool,
+    row_limit: Option<u32>,
 ) -> Result<ExecuteIter<'a>, Error> {
     let statement = conn.statements.get(query, persistent)?;
 
@@ -31,12 +33,20 @@ pub(crate) fn iter<'a>(
         statement,
         args,
         args_used: 0,
+        row_limit,
         goto_next: true,
         finished: false,
         buffered: VecDeque::with_capacity(1),
     })
 }
 
+impl ExecuteIter<'_> {
+    /// Reflects `begin`, `commit` and `rollback` as soon as they were run.
+    pub(crate) fn transaction_active(&self) -> bool {
+        *self.transaction_active
+    }
+}
+
 fn bind(
     statement: &mut oracle::Statement,
     arguments: &Option<OracleArguments>,
@@ -75,27 +85,27 @@ impl Iterator for ExecuteIter<'_> {
             return Some(item);
         }
 
-        if self.goto_next {
-            if let Some(command) = transaction_command(self.query) {
-                self.finished = true;
+        if self.goto_next
+            && let Some(command) = transaction_command(self.query)
+        {
+            self.finished = true;
 
-                if command == "begin" {
-                    *self.transaction_active = true;
-                    return Some(Ok(Either::Left(OracleQueryResult::default())));
-                }
+            if command == "begin" {
+                *self.transaction_active = true;
+                return Some(Ok(Either::Left(OracleQueryResult::default())));
+            }
 
-                let result = match command {
-                    "commit" => self.handle.connection().commit(),
-                    "rollback" => self.handle.connection().rollback(),
-                    _ => unreachable!(),
-                }
-                .map_err(|e| Error::from(e.to_string()))
-                .map(|_| {
-                    *self.transaction_active = false;
-                    Either::Left(OracleQueryResult::default())
-                });
-                return Some(result);
+            let result = match command {
+                "commit" => self.handle.connection().commit(),
+                "rollback" => self.handle.connection().rollback(),
+                _ => unreachable!(),
             }
+            .map_err(|e| Error::from(e.to_string()))
+            .map(|_| {
+                *self.transaction_active = false;
+                Either::Left(OracleQueryResult::default())
+            });
+            return Some(result);
         }
 
         let prepared = if self.goto_next {
@@ -114,6 +124,7 @@ impl Iterator for ExecuteIter<'_> {
             self.handle,
             prepared.handle.sql(),
             prepared.columns,
+            self.row_limit,
             |statement| bind(statement, &self.args, self.args_used),
         ) {
             Ok((items, args_used)) => {
@@ -123,15 +134,14 @@ impl Iterator for ExecuteIter<'_> {
             Err(e) => return Some(Err(e)),
         };
 
-        if !*self.transaction_active {
-            if let Err(e) = self
+        if !*self.transaction_active
+            && let Err(e) = self
                 .handle
                 .connection()
                 .commit()
                 .map_err(|e| Error::from(e.to_string()))
-            {
-                return Some(Err(e));
-            }
+        {
+            return Some(Err(e));
         }
 
         self.goto_next = true;
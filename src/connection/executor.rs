@@ -1,15 +1,24 @@
 use crate::connection::ConnectionState;
+use crate::connection::worker::{ConnectionWorker, RowChunk, send_chunked};
+use crate::decode::Decode;
 use crate::query::OracleQuery;
 use crate::{
-    OracleColumn, OracleQueryResult, OracleRow, OracleStatement, OracleTypeInfo, OracleValue,
+    OracleArguments, OracleColumn, OracleQueryResult, OracleRow, OracleStatement, OracleTypeInfo,
+    OracleValue,
 };
+use crossfire::{AsyncRx, spsc};
 use either::Either;
+use futures_core::Stream;
 use futures_core::future::BoxFuture;
 use futures_core::stream::BoxStream;
-use futures_util::{TryFutureExt, TryStreamExt, pin_mut};
+use futures_util::{StreamExt, TryFutureExt, TryStreamExt, pin_mut, stream};
 use rbdc::Error;
+use rbdc::db::{ExecResult, Row};
 use rbdc::try_stream;
+use rbs::Value;
+use std::iter;
 use std::sync::Arc;
+use std::thread;
 
 use super::OracleConnection;
 
@@ -39,12 +48,18 @@ pub(crate) fn run_prepared(
     handle: &mut crate::connection::ConnectionHandle,
     query: &str,
     columns: &mut Arc<Vec<OracleColumn>>,
+    row_limit: Option<u32>,
     bind: impl FnOnce(&mut oracle::Statement) -> Result<usize, Error>,
 ) -> Result<(Vec<StepResult>, usize), Error> {
-    let mut statement = build_statement(handle, query)?;
+    let mut statement = build_statement(handle, query, row_limit)?;
     let args_used_now = bind(&mut statement)?;
-    let mut results = execute_statement(&mut statement, columns)?;
+    let mut results = execute_statement(&mut statement, columns, row_limit)?;
     results.push(Ok(Either::Left(build_query_result(&statement)?)));
+    if row_limit.is_some() {
+        // Releases the cursor instead of leaving the unread rows open on the
+        // server until the statement is reused.
+        statement.close().map_err(to_error)?;
+    }
 
     Ok((results, args_used_now))
 }
@@ -53,24 +68,31 @@ fn count_bind_parameters(
     handle: &crate::connection::ConnectionHandle,
     query: &str,
 ) -> Result<usize, Error> {
-    let statement = build_statement(handle, query)?;
+    let statement = build_statement(handle, query, None)?;
     Ok(statement.bind_count())
 }
 
 fn build_statement(
     handle: &crate::connection::ConnectionHandle,
     query: &str,
+    row_limit: Option<u32>,
 ) -> Result<oracle::Statement, Error> {
     let arc = handle.as_arc();
-    arc.statement(query).build().map_err(to_error)
+    let mut builder = arc.statement(query);
+    if let Some(row_limit) = row_limit {
+        // Round trips return no more rows than will be read.
+        builder.fetch_array_size(row_limit).prefetch_rows(row_limit);
+    }
+    builder.build().map_err(to_error)
 }
 
 fn execute_statement(
     statement: &mut oracle::Statement,
     columns: &mut Arc<Vec<OracleColumn>>,
+    row_limit: Option<u32>,
 ) -> Result<Vec<StepResult>, Error> {
     match statement.query(&[]) {
-        Ok(rows) => collect_query_rows(rows, columns),
+        Ok(rows) => collect_query_rows(rows, columns, row_limit),
         Err(_) => {
             statement.execute(&[]).map_err(to_error)?;
             Ok(Vec::with_capacity(1))
@@ -81,11 +103,12 @@ fn execute_statement(
 fn collect_query_rows(
     rows: oracle::ResultSet<'_, oracle::Row>,
     columns: &mut Arc<Vec<OracleColumn>>,
+    row_limit: Option<u32>,
 ) -> Result<Vec<StepResult>, Error> {
     cache_columns(columns, rows.column_info());
 
     let mut results = Vec::with_capacity(columns.len());
-    for row in rows {
+    for row in rows.take(row_limit.map_or(usize::MAX, |limit| limit as usize)) {
         let row = row.map_err(to_error)?;
         results.push(collect_row(&row, columns).map(Either::Right));
     }
@@ -164,6 +187,105 @@ fn to_error(error: impl std::fmt::Display) -> Error {
     Error::from(error.to_string())
 }
 
+/// The results and rows of a query, one row at a time.
+pub(crate) fn unchunk(
+    rx: AsyncRx<spsc::Array<RowChunk>>,
+) -> impl Stream<Item = StepResult> + Send + 'static {
+    rx.into_stream().flat_map(|chunk| {
+        stream::iter(match chunk {
+            Ok(Either::Right(rows)) => {
+                Either::Left(rows.into_iter().map(|row| Ok(Either::Right(row))))
+            }
+            Ok(Either::Left(result)) => Either::Right(iter::once(Ok(Either::Left(result)))),
+            Err(e) => Either::Right(iter::once(Err(e))),
+        })
+    })
+}
+
+/// Moves `rows` from a producer thread to the async side the way the rows of
+/// a query travel, through [`send_chunked`] and [`unchunk`], and returns how
+/// many arrived. Not part of the API; measured by `benches/row_chunks.rs`.
+#[doc(hidden)]
+pub fn transfer_rows(
+    columns: &[&str],
+    rows: &[Vec<OracleValue>],
+    chunk_size: usize,
+    channel_size: usize,
+) -> usize {
+    let type_info = OracleTypeInfo::from_oracle_type(oracle::sql_type::OracleType::Varchar2(40));
+    let columns = Arc::new(
+        columns
+            .iter()
+            .enumerate()
+            .map(|(ordinal, name)| OracleColumn {
+                name: (*name).into(),
+                ordinal,
+                type_info: type_info.clone(),
+            })
+            .collect::<Vec<_>>(),
+    );
+    let (tx, rx) = spsc::bounded_blocking_async(channel_size);
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let rows = rows.iter().map(|values| {
+                Ok(Either::Right(OracleRow::new(
+                    Arc::clone(&columns),
+                    values.clone(),
+                )))
+            });
+            send_chunked(&tx, chunk_size, rows);
+        });
+        futures_executor::block_on(unchunk(rx).count())
+    })
+}
+
+/// `Connection::exec_rows` on `worker`; `sql` already uses `:n` placeholders.
+pub(crate) async fn exec_rows(
+    worker: &mut ConnectionWorker,
+    row_channel_size: usize,
+    sql: String,
+    params: Vec<Value>,
+) -> Result<BoxStream<'static, Result<Box<dyn Row>, Error>>, Error> {
+    let persistent = !params.is_empty();
+    let arguments = if persistent {
+        Some(OracleArguments::from_args(params)?)
+    } else {
+        None
+    };
+    let rx = worker
+        .execute(sql, arguments, row_channel_size, persistent, None)
+        .await?;
+    Ok(unchunk(rx)
+        .try_filter_map(
+            |step| async move { Ok(step.right().map(|row| Box::new(row) as Box<dyn Row>)) },
+        )
+        .boxed())
+}
+
+/// `Connection::exec` on `worker`; `sql` already uses `:n` placeholders.
+pub(crate) async fn exec(
+    worker: &mut ConnectionWorker,
+    row_channel_size: usize,
+    sql: String,
+    params: Vec<Value>,
+) -> Result<ExecResult, Error> {
+    let persistent = !params.is_empty();
+    let arguments = if persistent {
+        worker.prepare(&sql).await?;
+        Some(OracleArguments::from_args(params)?)
+    } else {
+        None
+    };
+    let result: OracleQueryResult = worker
+        .execute(sql, arguments, row_channel_size, persistent, None)
+        .await?
+        .into_stream()
+        .try_filter_map(|step| async move { Ok(step.left()) })
+        .try_collect()
+        .await?;
+    Ok(result.into_exec_result())
+}
+
 impl OracleConnection {
     pub fn fetch_many(
         &mut self,
@@ -173,9 +295,10 @@ impl OracleConnection {
         let persistent = query.persistent() && !query.arguments.is_empty();
         Box::pin(try_stream! {
             let arguments = query.take_arguments()?;
-            let stream = self.worker
-                .execute(sql, arguments, self.row_channel_size, persistent)
-                .map_ok(|rx| rx.into_stream())
+            let row_channel_size = self.row_channel_size;
+            let stream = self.worker().await?
+                .execute(sql, arguments, row_channel_size, persistent, None)
+                .map_ok(unchunk)
                 .try_flatten_stream();
             pin_mut!(stream);
             while let Some(item) = stream.try_next().await? {
@@ -185,36 +308,80 @@ impl OracleConnection {
         })
     }
 
+    /// The first row of `query`. Only that row is fetched from the server.
     pub fn fetch_optional(
         &mut self,
         query: OracleQuery,
     ) -> BoxFuture<'_, Result<Option<OracleRow>, Error>> {
-        let sql = query.sql().to_owned();
-        let persistent = query.persistent() && !query.arguments.is_empty();
         Box::pin(async move {
-            let arguments = query.take_arguments()?;
-            let stream = self
-                .worker
-                .execute(sql, arguments, self.row_channel_size, persistent)
-                .map_ok(|rx| rx.into_stream())
-                .try_flatten_stream();
-            pin_mut!(stream);
-            while let Some(item) = stream.try_next().await? {
-                if let Either::Right(row) = item {
-                    return Ok(Some(row));
-                }
+            let mut rows = self.fetch_limited(query, 1).await?;
+            Ok(rows.pop())
+        })
+    }
+
+    /// The only row of `query`; an error when it returns no rows or more than
+    /// one. At most two rows are fetched from the server.
+    pub fn fetch_one(&mut self, query: OracleQuery) -> BoxFuture<'_, Result<OracleRow, Error>> {
+        Box::pin(async move {
+            let mut rows = self.fetch_limited(query, 2).await?;
+            match rows.len() {
+                0 => Err(Error::from("query returned no rows")),
+                1 => Ok(rows.pop().expect("one row")),
+                _ => Err(Error::from("query returned more than one row")),
             }
-            Ok(None)
         })
     }
 
+    /// The first column of the only row of `query`, see [`Self::fetch_one`].
+    pub fn fetch_scalar<T: Decode>(
+        &mut self,
+        query: OracleQuery,
+    ) -> BoxFuture<'_, Result<T, Error>> {
+        Box::pin(async move { self.fetch_one(query).await?.try_get(0) })
+    }
+
+    /// Up to `row_limit` rows of the first statement of `query` that returns
+    /// any, with no more fetched than that.
+    async fn fetch_limited(
+        &mut self,
+        query: OracleQuery,
+        row_limit: u32,
+    ) -> Result<Vec<OracleRow>, Error> {
+        let sql = query.sql().to_owned();
+        let persistent = query.persistent() && !query.arguments.is_empty();
+        let arguments = query.take_arguments()?;
+        let row_channel_size = self.row_channel_size;
+        let stream = self
+            .worker()
+            .await?
+            .execute(
+                sql,
+                arguments,
+                row_channel_size,
+                persistent,
+                Some(row_limit),
+            )
+            .map_ok(unchunk)
+            .try_flatten_stream();
+        pin_mut!(stream);
+        let mut rows = Vec::with_capacity(row_limit as usize);
+        while let Some(item) = stream.try_next().await? {
+            match item {
+                Either::Right(row) => rows.push(row),
+                Either::Left(_) if !rows.is_empty() => break,
+                Either::Left(_) => {}
+            }
+        }
+        Ok(rows)
+    }
+
     pub fn prepare_with<'a>(
         &'a mut self,
         sql: &'a str,
         _parameters: &[OracleTypeInfo],
     ) -> BoxFuture<'a, Result<OracleStatement, Error>> {
         Box::pin(async move {
-            let statement = self.worker.prepare(sql).await?;
+            let statement = self.worker().await?.prepare(sql).await?;
             Ok(OracleStatement {
                 sql: sql.into(),
                 ..statement
@@ -0,0 +1,216 @@
+//! A backend standing in for an Oracle session in tests, for both the async
+//! and the blocking connection.
+
+use crate::blocking::BlockingBackend;
+use crate::connection::establish::EstablishParams;
+use crate::connection::info::SessionInfo;
+use crate::connection::worker::{Backend, Command, WorkerSharedState, send_chunked};
+use crate::options::CloseBehavior;
+use crate::{
+    OracleArguments, OracleColumn, OracleQueryResult, OracleRow, OracleStatement, OracleTypeInfo,
+    OracleValue,
+};
+use either::Either;
+use oracle::sql_type::OracleType;
+use rbdc::Error;
+use std::ops::ControlFlow;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Every statement prepared or executed by a `FakeBackend`.
+pub(crate) static EXECUTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Every query of a `FakeBackend` that ran outside a transaction or in one
+/// that was committed.
+pub(crate) static COMMITTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// The row limit of every query executed by a `FakeBackend`.
+pub(crate) static ROW_LIMITS: Mutex<Vec<(String, Option<u32>)>> = Mutex::new(Vec::new());
+
+/// Tracks `begin`, `commit` and `rollback`, takes 100ms for queries
+/// starting with `SLEEP` and answers every other query with the rows `1`,
+/// `2` and `3`, or `1` to `k` for `... WHERE n <= k`, chunked like a real
+/// session and cut at the row limit. Every other command succeeds; the
+/// match has no catch-all, so a new command must be handled here first.
+pub(crate) struct FakeBackend {
+    row_chunk_size: usize,
+    transaction_active: bool,
+    /// Queries of the open transaction, moved to `COMMITTED` by `commit`.
+    pending: Vec<String>,
+}
+
+impl FakeBackend {
+    pub(crate) fn new(params: &EstablishParams) -> Self {
+        Self {
+            row_chunk_size: params.row_chunk_size,
+            transaction_active: false,
+            pending: Vec::new(),
+        }
+    }
+
+    fn finish_transaction(&mut self, commit: bool) {
+        let pending = std::mem::take(&mut self.pending);
+        if commit {
+            COMMITTED.lock().unwrap().extend(pending);
+        }
+        self.transaction_active = false;
+    }
+
+    /// The results of `query`, the rows followed by one query result.
+    fn results(
+        &mut self,
+        query: &str,
+        row_limit: Option<u32>,
+    ) -> Vec<Result<Either<OracleQueryResult, OracleRow>, Error>> {
+        let result = Ok(Either::Left(OracleQueryResult::default()));
+        if matches!(query, "begin" | "commit" | "rollback") {
+            self.finish_transaction(query == "commit");
+            self.transaction_active = query == "begin";
+            return vec![result];
+        }
+
+        EXECUTED.lock().unwrap().push(query.to_owned());
+        if self.transaction_active {
+            self.pending.push(query.to_owned());
+        } else {
+            COMMITTED.lock().unwrap().push(query.to_owned());
+        }
+        ROW_LIMITS
+            .lock()
+            .unwrap()
+            .push((query.to_owned(), row_limit));
+        if query.starts_with("SLEEP") {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        let columns = Arc::new(vec![OracleColumn {
+            name: "N".into(),
+            ordinal: 0,
+            type_info: OracleTypeInfo::from_oracle_type(OracleType::Varchar2(10)),
+        }]);
+        let count = query
+            .strip_prefix("SELECT n FROM numbers WHERE n <= ")
+            .map_or(3, |n| n.parse().expect("row count"));
+        let row_limit = row_limit.map_or(usize::MAX, |limit| limit as usize);
+        (1..=count)
+            .take(row_limit)
+            .map(|n| {
+                let value = OracleValue::new(
+                    Some(n.to_string()),
+                    None,
+                    columns[0].type_info.clone(),
+                    false,
+                );
+                Ok(Either::Right(OracleRow::new(
+                    Arc::clone(&columns),
+                    vec![value],
+                )))
+            })
+            .chain([result])
+            .collect()
+    }
+}
+
+impl Backend for FakeBackend {
+    fn handle(&mut self, shared: &WorkerSharedState, cmd: Command) -> ControlFlow<()> {
+        match cmd {
+            Command::Prepare { query, tx } => {
+                EXECUTED.lock().unwrap().push(query.to_string());
+                tx.send(Ok(OracleStatement {
+                    sql: query.into(),
+                    parameters: 0,
+                    columns: Arc::default(),
+                }))
+                .ok();
+            }
+            Command::Execute {
+                query,
+                row_limit,
+                tx,
+                ..
+            } => {
+                let results = self.results(&query, row_limit);
+                shared
+                    .transaction_active
+                    .store(self.transaction_active, Ordering::Release);
+                send_chunked(&tx, self.row_chunk_size, results.into_iter());
+            }
+            Command::ClearCache { tx } => {
+                tx.send(()).ok();
+            }
+            Command::LoadSessionInfo { tx } => {
+                *shared.session.lock().unwrap() = Some(session());
+                tx.send(Ok(())).ok();
+            }
+            Command::Ping { tx }
+            | Command::ChangePassword { tx, .. }
+            | Command::SetAttribute { tx, .. } => {
+                tx.send(Ok(())).ok();
+            }
+            Command::Shutdown { tx } => {
+                tx.send(Ok(())).ok();
+                return ControlFlow::Break(());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn dropped(&mut self) {}
+
+    fn abandon(&mut self) {}
+}
+
+impl BlockingBackend for FakeBackend {
+    fn run(
+        &mut self,
+        sql: &str,
+        _arguments: Option<OracleArguments>,
+        _persistent: bool,
+        row_limit: Option<u32>,
+        f: &mut dyn FnMut(Either<OracleQueryResult, OracleRow>) -> bool,
+    ) -> Result<(), Error> {
+        for step in self.results(sql, row_limit) {
+            if !f(step?) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn transaction_active(&self) -> bool {
+        self.transaction_active
+    }
+
+    fn ping(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn end_transaction(&mut self, behavior: CloseBehavior) -> Result<(), Error> {
+        match behavior {
+            CloseBehavior::Error if self.transaction_active => {
+                return Err(Error::from("connection has an open transaction"));
+            }
+            behavior => self.finish_transaction(behavior == CloseBehavior::Commit),
+        }
+        Ok(())
+    }
+
+    fn end_dropped(&mut self, behavior: CloseBehavior) {
+        self.finish_transaction(behavior == CloseBehavior::Commit);
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+pub(crate) fn session() -> SessionInfo {
+    SessionInfo {
+        server_version: "23.4.0.24.5".to_owned(),
+        sid: 1,
+        serial: Some(1),
+        current_schema: "APP".to_owned(),
+        db_name: "FREE".to_owned(),
+        instance_name: "FREE".to_owned(),
+    }
+}
@@ -0,0 +1,136 @@
+use crate::connection::ConnectionStatus;
+use crate::error::has_error_code;
+use rbdc::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Snapshot of a connection's state, see [`OracleConnection::info`].
+///
+/// [`OracleConnection::info`]: crate::OracleConnection::info
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ConnectionInfo {
+    pub status: ConnectionStatus,
+    pub transaction_active: bool,
+    /// Address the session was established with.
+    pub host: String,
+    /// e.g. `19.3.0.0.0`.
+    ///
+    /// This and the following fields are read from the server and `None` until
+    /// that succeeded once; a broken session reports the last values read.
+    pub server_version: Option<String>,
+    pub sid: Option<u32>,
+    /// `SERIAL#` from `v$session`; `None` when the user cannot read it.
+    pub serial: Option<u32>,
+    /// Read again after every `ALTER SESSION` statement.
+    pub current_schema: Option<String>,
+    pub db_name: Option<String>,
+    pub instance_name: Option<String>,
+    /// Time since the last command finished.
+    pub idle: Duration,
+}
+
+/// The parts of [`ConnectionInfo`] read from the server, on the first
+/// `info()` of a session.
+#[derive(Debug, Clone)]
+pub(crate) struct SessionInfo {
+    pub(crate) server_version: String,
+    pub(crate) sid: u32,
+    pub(crate) serial: Option<u32>,
+    pub(crate) current_schema: String,
+    pub(crate) db_name: String,
+    pub(crate) instance_name: String,
+}
+
+const SESSION_COLUMNS: &str = "TO_NUMBER(SYS_CONTEXT('USERENV', 'SID')), \
+     SYS_CONTEXT('USERENV', 'CURRENT_SCHEMA'), SYS_CONTEXT('USERENV', 'DB_NAME'), \
+     SYS_CONTEXT('USERENV', 'INSTANCE_NAME')";
+
+/// `SYS_CONTEXT` has no serial number, `v$session` needs a grant.
+const SERIAL_COLUMN: &str =
+    "(SELECT serial# FROM v$session WHERE sid = SYS_CONTEXT('USERENV', 'SID'))";
+
+/// Table or view does not exist, insufficient privileges.
+const NOT_READABLE: &[&str] = &["ORA-00942", "ORA-01031"];
+
+/// SID, current schema, database and instance name, serial number.
+type SessionRow = (u32, String, String, String, Option<u32>);
+
+/// Cleared once `v$session` turned out not to be readable, so later sessions
+/// skip it instead of failing first.
+static SERIAL_READABLE: AtomicBool = AtomicBool::new(true);
+
+impl SessionInfo {
+    /// One query; ODPI caches the server version of the session.
+    pub(crate) fn query(conn: &oracle::Connection) -> Result<Self, Error> {
+        let to_error = |e: oracle::Error| Error::from(e.to_string());
+        let (version, _) = conn.server_version().map_err(to_error)?;
+
+        let (sid, current_schema, db_name, instance_name, serial) =
+            match Self::query_with_serial(conn) {
+                Some(row) => row?,
+                None => {
+                    let (sid, schema, db_name, instance_name) = conn
+                        .query_row_as::<(u32, String, String, String)>(
+                            &format!("SELECT {SESSION_COLUMNS} FROM dual"),
+                            &[],
+                        )
+                        .map_err(to_error)?;
+                    (sid, schema, db_name, instance_name, None)
+                }
+            };
+
+        Ok(Self {
+            server_version: version.to_string(),
+            sid,
+            serial,
+            current_schema,
+            db_name,
+            instance_name,
+        })
+    }
+
+    /// `None` when `v$session` is not readable.
+    fn query_with_serial(conn: &oracle::Connection) -> Option<Result<SessionRow, Error>> {
+        if !SERIAL_READABLE.load(Ordering::Relaxed) {
+            return None;
+        }
+        let row = conn
+            .query_row_as::<SessionRow>(
+                &format!("SELECT {SESSION_COLUMNS}, {SERIAL_COLUMN} FROM dual"),
+                &[],
+            )
+            .map_err(|e| Error::from(e.to_string()));
+        match row {
+            Err(e) if has_error_code(&e, NOT_READABLE) => {
+                log::debug!("SERIAL# not available: {e}");
+                SERIAL_READABLE.store(false, Ordering::Relaxed);
+                None
+            }
+            row => Some(row),
+        }
+    }
+}
+
+/// Whether `query` may change what [`SessionInfo`] tracks.
+pub(crate) fn alters_session(query: &str) -> bool {
+    let mut words = query.split_whitespace();
+    matches!(
+        (words.next(), words.next()),
+        (Some(alter), Some(session))
+            if alter.eq_ignore_ascii_case("alter") && session.eq_ignore_ascii_case("session")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::alters_session;
+
+    #[test]
+    fn test_alters_session() {
+        assert!(alters_session("ALTER SESSION SET CURRENT_SCHEMA = app"));
+        assert!(alters_session("  alter\n session set edition = e2"));
+        assert!(!alters_session("ALTER TABLE t ADD c NUMBER"));
+        assert!(!alters_session("SELECT 'alter session' FROM dual"));
+    }
+}
@@ -2,27 +2,63 @@ use futures_core::future::BoxFuture;
 use futures_util::future;
 use rbdc::Error;
 use rbdc::StatementCache;
+use rbdc::db::Connection;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 
 pub(crate) use handle::ConnectionHandle;
 
 use crate::OracleConnectOptions;
-use crate::connection::establish::EstablishParams;
-use crate::connection::worker::ConnectionWorker;
+use crate::connection::establish::{EstablishParams, with_options};
+use crate::connection::worker::{ConnectionWorker, WeakWorker};
+use crate::options::SecretString;
 use crate::statement::VirtualStatement;
 
-mod establish;
-mod execute;
-mod executor;
+pub(crate) mod establish;
+pub(crate) mod execute;
+pub(crate) mod executor;
+#[cfg(test)]
+pub(crate) mod fake;
 mod handle;
-mod worker;
+mod info;
+mod shared;
+mod threads;
+mod timer;
+pub(crate) mod worker;
 
-pub use worker::Command;
+#[doc(hidden)]
+pub use executor::transfer_rows;
+pub use info::ConnectionInfo;
+pub use shared::OracleConnectionHandle;
+pub use worker::{Command, SessionAttribute};
 
 pub struct OracleConnection {
     pub(crate) worker: ConnectionWorker,
     pub(crate) row_channel_size: usize,
+    /// Kept to re-establish the session, see [`OracleConnection::reconnect`].
+    pub(crate) options: Arc<OracleConnectOptions>,
+    /// The session handles follow, replaced on reconnect.
+    pub(crate) current: Arc<Mutex<WeakWorker>>,
+    /// Starts the worker for one set of parameters, again on reconnect.
+    connect: Connect,
+    reconnects: u32,
+}
+
+/// Starts a worker for one set of parameters, see
+/// [`OracleConnection::establish_with`].
+pub(crate) type Connect =
+    fn(EstablishParams) -> BoxFuture<'static, Result<ConnectionWorker, Error>>;
+
+/// Health of the session behind an [`OracleConnection`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConnectionStatus {
+    Connected,
+    /// The session was lost (ORA-03113, ORA-03114, ORA-03135, DPI-1080).
+    /// Commands fail until the connection is re-established, which happens
+    /// automatically with `OracleConnectOptions::reconnect` unless a
+    /// transaction was open.
+    Broken,
 }
 
 unsafe impl Sync for OracleConnection {}
@@ -40,18 +76,54 @@ pub(crate) struct Statements {
 
 impl OracleConnection {
     pub(crate) async fn establish(options: &OracleConnectOptions) -> Result<Self, Error> {
-        let params = EstablishParams::from_options(options)?;
-        let worker = ConnectionWorker::establish(params).await?;
+        Self::establish_with(options, |params| {
+            Box::pin(ConnectionWorker::establish(params))
+        })
+        .await
+    }
+
+    /// Runs the retry, failover and provider logic around `connect`, which
+    /// starts the worker for one set of parameters.
+    pub(crate) async fn establish_with(
+        options: &OracleConnectOptions,
+        connect: Connect,
+    ) -> Result<Self, Error> {
+        let worker = with_options(options, connect).await?;
+        let mut options = options.clone();
+        // The logon changed the password, so later ones use the new one.
+        if let Some(new_password) = options.new_password.take() {
+            options.password = new_password;
+        }
         Ok(Self {
+            current: Arc::new(Mutex::new(worker.downgrade())),
             worker,
             row_channel_size: options.row_channel_size,
+            options: Arc::new(options),
+            connect,
+            reconnects: 0,
         })
     }
+
+    /// The worker, after transparently re-establishing a broken session when
+    /// `reconnect` is enabled and no transaction was lost with it.
+    pub(crate) async fn worker(&mut self) -> Result<&mut ConnectionWorker, Error> {
+        if self.worker.is_broken() && self.options.reconnect {
+            if self.worker.transaction_active() {
+                return Err(Error::from(
+                    "connection lost during a transaction, call reconnect() once it is handled",
+                ));
+            }
+            self.reconnect().await?;
+        }
+        Ok(&mut self.worker)
+    }
 }
 
 impl Debug for OracleConnection {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("OracleConnection")
+            .field("host", &self.worker.host)
+            .field("status", &self.status())
             .field("row_channel_size", &self.row_channel_size)
             .field("cached_statements_size", &self.cached_statements_size())
             .finish()
@@ -63,8 +135,88 @@ impl OracleConnection {
         self.worker.shutdown().await
     }
 
+    /// Checks the session. When it turns out to be lost and `reconnect` is
+    /// enabled, a new session is established instead of failing, unless a
+    /// transaction was open.
     pub fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
-        Box::pin(self.worker.ping())
+        Box::pin(async move {
+            match self.worker().await?.ping().await {
+                Err(e)
+                    if self.worker.is_broken()
+                        && self.options.reconnect
+                        && !self.worker.transaction_active() =>
+                {
+                    log::warn!("ping failed, reconnecting: {e}");
+                    self.reconnect().await
+                }
+                result => result,
+            }
+        })
+    }
+
+    pub fn status(&self) -> ConnectionStatus {
+        if self.worker.is_broken() {
+            ConnectionStatus::Broken
+        } else {
+            ConnectionStatus::Connected
+        }
+    }
+
+    /// State of the connection and its session, for monitoring and eviction
+    /// decisions. Status, transaction, host and idle time are known locally.
+    /// The session details cost one round trip on the first call and after
+    /// `ALTER SESSION` statements; when that read fails, or the session is
+    /// broken, they keep their last values (`None` if never read) and the
+    /// failure is logged, so a broken connection still reports `Broken`.
+    pub fn info(&mut self) -> BoxFuture<'_, ConnectionInfo> {
+        Box::pin(async move {
+            let idle = self.worker.shared.last_used.lock().unwrap().elapsed();
+            let session = self.worker.session_info().await;
+            ConnectionInfo {
+                status: self.status(),
+                transaction_active: self.worker.transaction_active(),
+                host: self.worker.host.clone(),
+                server_version: session.as_ref().map(|s| s.server_version.clone()),
+                sid: session.as_ref().map(|s| s.sid),
+                serial: session.as_ref().and_then(|s| s.serial),
+                current_schema: session.as_ref().map(|s| s.current_schema.clone()),
+                db_name: session.as_ref().map(|s| s.db_name.clone()),
+                instance_name: session.map(|s| s.instance_name),
+                idle,
+            }
+        })
+    }
+
+    /// How often the session was re-established.
+    pub fn reconnects(&self) -> u32 {
+        self.reconnects
+    }
+
+    /// Replaces the session with a new one established from the original
+    /// options, replaying the session settings (NLS, tracing attributes,
+    /// `after_connect`). A transaction still open on a healthy session is
+    /// ended according to `close_behavior`; one lost with a broken session is
+    /// gone. Handles from [`OracleConnection::handle`] move to the new session
+    /// too.
+    pub fn reconnect(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let fresh = OracleConnection::establish_with(&self.options, self.connect).await?;
+            let mut stale = std::mem::replace(&mut self.worker, fresh.worker);
+            *self.current.lock().unwrap() = self.worker.downgrade();
+            if let Err(e) = stale.shutdown().await {
+                log::debug!("closing the replaced session failed: {e}");
+            }
+            self.reconnects += 1;
+            log::info!("reconnected to {}", self.worker.host);
+            Ok(())
+        })
+    }
+
+    /// The address this connection was established with: `host:port` for
+    /// Easy Connect strings (the failover host actually used, see
+    /// `OracleConnectOptions::hosts`), otherwise the connect string.
+    pub fn host(&self) -> &str {
+        &self.worker.host
     }
 
     pub fn cached_statements_size(&self) -> usize {
@@ -76,7 +228,98 @@ impl OracleConnection {
 
     pub fn clear_cached_statements(&mut self) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
-            self.worker.clear_cache().await?;
+            self.worker().await?.clear_cache().await?;
+            Ok(())
+        })
+    }
+
+    /// Sets `MODULE`; sent with the next round trip.
+    pub fn set_module<'a>(&'a mut self, module: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.set_attribute(SessionAttribute::Module, module)
+    }
+
+    /// Sets `ACTION`; sent with the next round trip.
+    pub fn set_action<'a>(&'a mut self, action: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.set_attribute(SessionAttribute::Action, action)
+    }
+
+    /// Sets `CLIENT_IDENTIFIER`; sent with the next round trip.
+    pub fn set_client_identifier<'a>(
+        &'a mut self,
+        client_identifier: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.set_attribute(SessionAttribute::ClientIdentifier, client_identifier)
+    }
+
+    /// Sets `CLIENT_INFO`; sent with the next round trip.
+    pub fn set_client_info<'a>(
+        &'a mut self,
+        client_info: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.set_attribute(SessionAttribute::ClientInfo, client_info)
+    }
+
+    /// Starts (or with `""` ends) a monitored database operation; sent with the
+    /// next round trip.
+    pub fn set_db_op<'a>(&'a mut self, db_op: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.set_attribute(SessionAttribute::DbOp, db_op)
+    }
+
+    /// Changes the password of the session user, e.g. for rotation tooling.
+    /// Later reconnects log on with `new_password`.
+    pub fn change_password<'a>(
+        &'a mut self,
+        old_password: &'a str,
+        new_password: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.worker()
+                .await?
+                .change_password(old_password, new_password)
+                .await?;
+            Arc::make_mut(&mut self.options).password = SecretString::new(new_password);
+            Ok(())
+        })
+    }
+
+    fn set_attribute<'a>(
+        &'a mut self,
+        attribute: SessionAttribute,
+        value: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.worker().await?.set_attribute(attribute, value).await?;
+            // replayed by reconnects; a monitored operation ends with its session
+            let options = Arc::make_mut(&mut self.options);
+            let recorded = match attribute {
+                SessionAttribute::Module => &mut options.module,
+                SessionAttribute::Action => &mut options.action,
+                SessionAttribute::ClientIdentifier => &mut options.client_identifier,
+                SessionAttribute::ClientInfo => &mut options.client_info,
+                SessionAttribute::DbOp => return Ok(()),
+            };
+            *recorded = Some(value.to_owned());
+            Ok(())
+        })
+    }
+
+    /// Switches the session to another edition with `ALTER SESSION SET EDITION`.
+    ///
+    /// Oracle refuses this while a transaction is open. Reconnects log on with
+    /// the new edition, as they keep the tracing attributes set here.
+    pub fn set_edition<'a>(&'a mut self, edition: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            if edition.is_empty()
+                || !edition
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '#'))
+            {
+                return Err(Error::from(format!("invalid edition name: {edition}")));
+            }
+            self.exec(&format!("ALTER SESSION SET EDITION = {edition}"), vec![])
+                .await?;
+            // later reconnects log on with it
+            Arc::make_mut(&mut self.options).edition = Some(edition.to_owned());
             Ok(())
         })
     }
@@ -135,3 +378,561 @@ impl Statements {
         self.temp = None;
     }
 }
+
+/// The async layer runs on any executor; these drive a fake backend with
+/// `futures-executor` and `smol` instead of tokio.
+#[cfg(test)]
+mod tests {
+    use super::fake::{EXECUTED, FakeBackend, ROW_LIMITS};
+    use super::shared::TRANSACTION_LOST;
+    use super::{ConnectionStatus, OracleConnection, SessionAttribute};
+    use crate::OracleConnectOptions;
+    use crate::connection::establish::EstablishParams;
+    use crate::connection::worker::{ConnectionWorker, TRANSACTION_HELD};
+    use crate::query::OracleQuery;
+    use either::Either;
+    use futures_util::{FutureExt, TryStreamExt};
+    use rbdc::Error;
+    use rbdc::db::Connection;
+    use rbs::Value;
+    use std::sync::Mutex;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    fn start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+        Ok(FakeBackend::new(params))
+    }
+
+    async fn connect(options: &OracleConnectOptions) -> Result<OracleConnection, Error> {
+        OracleConnection::establish_with(options, |params| {
+            Box::pin(ConnectionWorker::establish_with(params, start))
+        })
+        .await
+    }
+
+    async fn query_numbers(conn: &mut dyn Connection) -> Vec<Value> {
+        let rows: Vec<_> = conn
+            .exec_rows("SELECT n FROM numbers", vec![])
+            .await
+            .expect("exec_rows")
+            .try_collect()
+            .await
+            .expect("rows");
+        rows.into_iter()
+            .map(|mut row| row.get(0).expect("value"))
+            .collect()
+    }
+
+    fn numbers() -> Vec<Value> {
+        ["1", "2", "3"]
+            .map(|n| Value::String(n.to_owned()))
+            .to_vec()
+    }
+
+    #[test]
+    fn test_dedicated_thread_without_tokio() {
+        futures_executor::block_on(async {
+            let mut conn = connect(&OracleConnectOptions::new())
+                .await
+                .expect("connect");
+            conn.ping().await.expect("ping");
+            assert_eq!(query_numbers(&mut conn).await, numbers());
+            conn.clear_cached_statements().await.expect("clear cache");
+            assert!(conn.worker.shared.session.lock().unwrap().is_none());
+            let info = conn.info().await;
+            assert_eq!(info.server_version.as_deref(), Some("23.4.0.24.5"));
+            assert!(conn.worker.shared.session.lock().unwrap().is_some());
+            conn.close().await.expect("close");
+        });
+    }
+
+    #[test]
+    fn test_info_of_broken_session() {
+        futures_executor::block_on(async {
+            let mut conn = connect(&OracleConnectOptions::new())
+                .await
+                .expect("connect");
+            let mut never_read = connect(&OracleConnectOptions::new())
+                .await
+                .expect("connect");
+            assert_eq!(conn.info().await.sid, Some(1));
+
+            for conn in [&mut conn, &mut never_read] {
+                conn.worker
+                    .shared
+                    .session_stale
+                    .store(true, Ordering::Release);
+                conn.worker.shared.broken.store(true, Ordering::Release);
+            }
+            let info = conn.info().await;
+            assert_eq!(info.status, ConnectionStatus::Broken);
+            assert_eq!(info.current_schema.as_deref(), Some("APP"));
+            let info = never_read.info().await;
+            assert_eq!(info.status, ConnectionStatus::Broken);
+            assert_eq!(info.server_version, None);
+        });
+    }
+
+    #[test]
+    fn test_shared_threads_without_tokio() {
+        smol::block_on(async {
+            let options = OracleConnectOptions::new()
+                .worker_threads(1)
+                .worker_thread_name("test-fake");
+            let mut a = connect(&options).await.expect("connect");
+            let mut b = connect(&options).await.expect("connect");
+            assert_eq!(query_numbers(&mut a).await, numbers());
+
+            let results: Vec<_> = b
+                .fetch_many(OracleQuery {
+                    statement: Either::Left("SELECT n FROM numbers".to_owned()),
+                    arguments: vec![],
+                    persistent: false,
+                })
+                .try_collect()
+                .await
+                .expect("fetch_many");
+            assert_eq!(results.len(), 4);
+            assert!(results[3].is_left());
+
+            a.close().await.expect("close");
+            b.close().await.expect("close");
+        });
+    }
+
+    #[test]
+    fn test_connect_timeout_without_tokio() {
+        use crate::connection::establish::CONNECT_TIMEOUT;
+        use std::time::Duration;
+
+        fn slow_start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(FakeBackend::new(params))
+        }
+
+        let options = OracleConnectOptions::new().connect_timeout(Duration::from_millis(20));
+        let result =
+            futures_executor::block_on(OracleConnection::establish_with(&options, |params| {
+                Box::pin(ConnectionWorker::establish_with(params, slow_start))
+            }));
+        let error = result.expect_err("timed out").to_string();
+        assert!(error.starts_with(CONNECT_TIMEOUT), "{error}");
+    }
+
+    #[test]
+    fn test_retry_without_tokio() {
+        use crate::options::ConnectRetry;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+        fn flaky_start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+            if ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(Error::from("ORA-12541: TNS:no listener"));
+            }
+            Ok(FakeBackend::new(params))
+        }
+
+        let options = OracleConnectOptions::new().connect_retry(
+            ConnectRetry::new(2).backoff(Duration::from_millis(1), Duration::from_millis(1)),
+        );
+        smol::block_on(async {
+            let mut conn = OracleConnection::establish_with(&options, |params| {
+                Box::pin(ConnectionWorker::establish_with(params, flaky_start))
+            })
+            .await
+            .expect("connect");
+            assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 2);
+            assert_eq!(query_numbers(&mut conn).await, numbers());
+            conn.close().await.expect("close");
+        });
+    }
+
+    #[test]
+    fn test_handles_share_the_session() {
+        let mut conn =
+            futures_executor::block_on(connect(&OracleConnectOptions::new())).expect("connect");
+        let handle = conn.handle();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mut handle = handle.clone();
+                std::thread::spawn(move || {
+                    futures_executor::block_on(async {
+                        for _ in 0..10 {
+                            assert_eq!(query_numbers(&mut handle).await, numbers());
+                        }
+                    })
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("queries");
+        }
+        drop(handle);
+        futures_executor::block_on(conn.close()).expect("close");
+    }
+
+    #[test]
+    fn test_transaction_owned_by_one_handle() {
+        smol::block_on(async {
+            let mut conn = connect(&OracleConnectOptions::new())
+                .await
+                .expect("connect");
+            let mut owner = conn.handle();
+            let mut other = owner.clone();
+
+            owner.begin().await.expect("begin");
+            assert!(owner.holds_transaction());
+            assert!(!other.holds_transaction());
+            assert_eq!(query_numbers(&mut owner).await, numbers());
+
+            let error = other.exec("DELETE FROM numbers", vec![]).await;
+            assert_eq!(error.expect_err("foreign").to_string(), TRANSACTION_HELD);
+            let error = conn.close().await.expect_err("close during transaction");
+            assert_eq!(error.to_string(), TRANSACTION_HELD);
+            other.ping().await.expect("ping is not transactional");
+
+            owner.commit().await.expect("commit");
+            assert!(!owner.holds_transaction());
+            assert_eq!(query_numbers(&mut other).await, numbers());
+            conn.close().await.expect("close");
+        });
+    }
+
+    #[test]
+    fn test_dropped_handle_releases_transaction() {
+        smol::block_on(async {
+            let options = OracleConnectOptions::new()
+                .worker_threads(1)
+                .worker_thread_name("test-handles");
+            let mut conn = connect(&options).await.expect("connect");
+            let mut owner = conn.handle();
+            owner.begin().await.expect("begin");
+            drop(owner);
+
+            // the rollback is queued ahead of this query
+            assert_eq!(query_numbers(&mut conn).await, numbers());
+            assert!(!conn.worker.transaction_active());
+            conn.close().await.expect("close");
+        });
+    }
+
+    #[test]
+    fn test_rows_arrive_in_order_for_any_chunk_size() {
+        for row_chunk_size in [0, 1, 2, 3, 64] {
+            let options = OracleConnectOptions::new().row_chunk_size(row_chunk_size);
+            futures_executor::block_on(async {
+                let mut conn = connect(&options).await.expect("connect");
+                assert_eq!(query_numbers(&mut conn).await, numbers());
+
+                let steps: Vec<_> = conn
+                    .fetch_many(OracleQuery {
+                        statement: Either::Left("SELECT n FROM numbers".to_owned()),
+                        arguments: vec![],
+                        persistent: false,
+                    })
+                    .try_collect()
+                    .await
+                    .expect("fetch_many");
+                let kinds: Vec<bool> = steps.iter().map(Either::is_right).collect();
+                assert_eq!(kinds, [true, true, true, false]);
+                conn.close().await.expect("close");
+            });
+        }
+    }
+
+    fn executed(query: &str) -> bool {
+        EXECUTED
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|executed| executed == query)
+    }
+
+    #[test]
+    fn test_commands_dropped_while_queued_are_skipped() {
+        for worker_threads in [None, Some(1)] {
+            let mut options = OracleConnectOptions::new().worker_thread_name("test-cancel");
+            options.worker_threads = worker_threads;
+            futures_executor::block_on(async {
+                let mut conn = connect(&options).await.expect("connect");
+                let busy = conn
+                    .worker
+                    .execute("SLEEP".to_owned(), None, 1, false, None)
+                    .await
+                    .expect("queued");
+
+                let insert = format!("INSERT INTO audit VALUES ({worker_threads:?})");
+                let abandoned = conn
+                    .worker
+                    .execute(insert.clone(), None, 1, false, None)
+                    .await
+                    .expect("queued");
+                drop(abandoned);
+                let prepare = format!("UPDATE audit SET n = 1 -- {worker_threads:?}");
+                assert!(conn.worker.prepare(&prepare).now_or_never().is_none());
+
+                drop(busy);
+                assert_eq!(query_numbers(&mut conn).await, numbers());
+                assert!(!executed(&insert), "{insert} ran after being dropped");
+                assert!(!executed(&prepare), "{prepare} ran after being dropped");
+                conn.close().await.expect("close");
+            });
+        }
+    }
+
+    fn row_limit(query: &str) -> Option<u32> {
+        ROW_LIMITS
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(executed, _)| executed == query)
+            .and_then(|(_, row_limit)| *row_limit)
+    }
+
+    #[test]
+    fn test_single_row_queries_fetch_only_needed_rows() {
+        let options = OracleConnectOptions::new().worker_thread_name("test-single-row");
+        futures_executor::block_on(async {
+            let mut conn = connect(&options).await.expect("connect");
+            let query = |sql: &str| OracleQuery {
+                statement: Either::Left(sql.to_owned()),
+                arguments: vec![],
+                persistent: false,
+            };
+
+            let first = conn
+                .fetch_optional(query("SELECT n FROM numbers WHERE n <= 5"))
+                .await
+                .expect("fetch_optional")
+                .expect("a row");
+            assert_eq!(first.try_get::<i64>(0).expect("decode"), 1);
+            assert_eq!(row_limit("SELECT n FROM numbers WHERE n <= 5"), Some(1));
+            let none = conn
+                .fetch_optional(query("SELECT n FROM numbers WHERE n <= 0"))
+                .await
+                .expect("fetch_optional");
+            assert!(none.is_none());
+
+            let one = conn
+                .fetch_one(query("SELECT n FROM numbers WHERE n <= 1"))
+                .await
+                .expect("fetch_one");
+            assert_eq!(one.try_get::<String>(0).expect("decode"), "1");
+            assert_eq!(row_limit("SELECT n FROM numbers WHERE n <= 1"), Some(2));
+            let error = conn
+                .fetch_one(query("SELECT n FROM numbers WHERE n <= 0"))
+                .await
+                .expect_err("no rows");
+            assert!(error.to_string().contains("no rows"), "{error}");
+            let error = conn
+                .fetch_one(query("SELECT n FROM numbers WHERE n <= 4"))
+                .await
+                .expect_err("several rows");
+            assert!(error.to_string().contains("more than one row"), "{error}");
+
+            let scalar: u64 = conn
+                .fetch_scalar(query("SELECT n FROM numbers WHERE n <= 1"))
+                .await
+                .expect("fetch_scalar");
+            assert_eq!(scalar, 1);
+
+            let rows: Vec<_> = conn
+                .fetch_many(query("SELECT n FROM numbers WHERE n <= 4"))
+                .try_collect()
+                .await
+                .expect("fetch_many");
+            assert_eq!(rows.len(), 5);
+            assert_eq!(row_limit("SELECT n FROM numbers WHERE n <= 4"), None);
+            conn.close().await.expect("close");
+        });
+    }
+
+    /// `(password, new_password)` of every logon by `recording_start`.
+    static LOGONS: Mutex<Vec<(String, Option<String>)>> = Mutex::new(Vec::new());
+
+    fn recording_start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+        LOGONS.lock().unwrap().push((
+            params.password.expose_secret().to_owned(),
+            params
+                .new_password
+                .as_ref()
+                .map(|password| password.expose_secret().to_owned()),
+        ));
+        start(params)
+    }
+
+    /// Edition and tracing attributes of every logon by `session_start`.
+    type SessionSetup = (Option<String>, Vec<(SessionAttribute, String)>);
+    static SESSION_SETUPS: Mutex<Vec<SessionSetup>> = Mutex::new(Vec::new());
+
+    fn session_start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+        SESSION_SETUPS
+            .lock()
+            .unwrap()
+            .push((params.edition.clone(), params.session_attributes.clone()));
+        start(params)
+    }
+
+    #[test]
+    fn test_reconnect_keeps_edition_and_attributes() {
+        let options = OracleConnectOptions::new()
+            .module("batch")
+            .worker_thread_name("test-edition");
+        futures_executor::block_on(async {
+            let mut conn = OracleConnection::establish_with(&options, |params| {
+                Box::pin(ConnectionWorker::establish_with(params, session_start))
+            })
+            .await
+            .expect("connect");
+            conn.set_edition("release_2").await.expect("set_edition");
+            conn.set_module("import").await.expect("set_module");
+            conn.set_action("load").await.expect("set_action");
+            conn.set_db_op("nightly").await.expect("set_db_op");
+            conn.reconnect().await.expect("reconnect");
+            conn.close().await.expect("close");
+        });
+        assert_eq!(
+            *SESSION_SETUPS.lock().unwrap(),
+            [
+                (None, vec![(SessionAttribute::Module, "batch".to_owned())]),
+                (
+                    Some("release_2".to_owned()),
+                    vec![
+                        (SessionAttribute::Module, "import".to_owned()),
+                        (SessionAttribute::Action, "load".to_owned()),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconnect_after_password_change() {
+        let options = OracleConnectOptions::new()
+            .username("app")
+            .password("expired")
+            .new_password("rotated")
+            .worker_thread_name("test-new-password");
+        futures_executor::block_on(async {
+            let mut conn = OracleConnection::establish_with(&options, |params| {
+                Box::pin(ConnectionWorker::establish_with(params, recording_start))
+            })
+            .await
+            .expect("connect");
+            conn.reconnect().await.expect("reconnect");
+            conn.change_password("rotated", "rotated-again")
+                .await
+                .expect("change_password");
+            conn.reconnect().await.expect("reconnect");
+            conn.close().await.expect("close");
+        });
+        assert_eq!(
+            *LOGONS.lock().unwrap(),
+            [
+                ("expired".to_owned(), Some("rotated".to_owned())),
+                ("rotated".to_owned(), None),
+                ("rotated-again".to_owned(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_nested_in_open_stream_on_one_shared_thread() {
+        let options = OracleConnectOptions::new()
+            .worker_threads(1)
+            .worker_thread_name("test-nested")
+            .row_channel_size(1)
+            .row_chunk_size(1);
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            futures_executor::block_on(async {
+                let mut a = connect(&options).await.expect("connect");
+                let mut b = connect(&options).await.expect("connect");
+                // fills the row channel and leaves the query waiting for the
+                // consumer on the only shared thread
+                let mut outer = a
+                    .exec_rows("SELECT n FROM numbers", vec![])
+                    .await
+                    .expect("exec_rows");
+                let first = outer.try_next().await.expect("row").expect("a row");
+                assert_eq!(query_numbers(&mut b).await, numbers());
+                let rest: Vec<_> = outer.try_collect().await.expect("rows");
+                assert_eq!(rest.len(), 2);
+                drop(first);
+                a.close().await.expect("close");
+                b.close().await.expect("close");
+            });
+            done_tx.send(()).unwrap();
+        });
+        done_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("nested query deadlocked");
+    }
+
+    #[test]
+    fn test_hung_logon_leaves_the_shared_threads() {
+        use crate::connection::establish::CONNECT_TIMEOUT;
+
+        static RELEASE: Mutex<Option<std::sync::mpsc::Receiver<()>>> = Mutex::new(None);
+
+        fn hung_start(params: &EstablishParams) -> Result<FakeBackend, Error> {
+            let release = RELEASE.lock().unwrap().take().expect("one hung logon");
+            release.recv().ok();
+            Ok(FakeBackend::new(params))
+        }
+
+        let (release_tx, release_rx) = std::sync::mpsc::channel();
+        *RELEASE.lock().unwrap() = Some(release_rx);
+        let options = OracleConnectOptions::new()
+            .worker_threads(1)
+            .worker_thread_name("test-hung-logon");
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            futures_executor::block_on(async {
+                let hung = options.clone().connect_timeout(Duration::from_millis(20));
+                let result = OracleConnection::establish_with(&hung, |params| {
+                    Box::pin(ConnectionWorker::establish_with(params, hung_start))
+                })
+                .await;
+                let error = result.expect_err("timed out").to_string();
+                assert!(error.starts_with(CONNECT_TIMEOUT), "{error}");
+
+                let mut conn = connect(&options).await.expect("connect");
+                assert_eq!(query_numbers(&mut conn).await, numbers());
+                conn.close().await.expect("close");
+            });
+            done_tx.send(()).unwrap();
+        });
+        let done = done_rx.recv_timeout(Duration::from_secs(10));
+        release_tx.send(()).ok();
+        done.expect("hung logon blocked the shared thread");
+    }
+
+    #[test]
+    fn test_handles_follow_reconnect() {
+        let options = OracleConnectOptions::new().worker_thread_name("test-follow");
+        futures_executor::block_on(async {
+            let mut conn = connect(&options).await.expect("connect");
+            let mut handle = conn.handle();
+            conn.reconnect().await.expect("reconnect");
+            // the old session was closed by the reconnect
+            assert_eq!(query_numbers(&mut handle).await, numbers());
+
+            let mut in_transaction = conn.handle();
+            in_transaction.exec("begin", vec![]).await.expect("begin");
+            conn.reconnect().await.expect("reconnect");
+            let error = in_transaction
+                .exec("SELECT n FROM numbers", vec![])
+                .await
+                .expect_err("transaction left behind");
+            assert!(error.to_string().contains(TRANSACTION_LOST), "{error}");
+            assert!(!in_transaction.holds_transaction());
+            assert_eq!(query_numbers(&mut in_transaction).await, numbers());
+            assert_eq!(query_numbers(&mut handle).await, numbers());
+            conn.close().await.expect("close");
+        });
+    }
+}
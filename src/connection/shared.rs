@@ -0,0 +1,156 @@
+use crate::connection::executor::{exec, exec_rows, unchunk};
+use crate::connection::worker::{ConnectionWorker, WeakWorker};
+use crate::query::OracleQuery;
+use crate::{OracleConnection, OracleQueryResult, OracleRow};
+use either::Either;
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use futures_util::TryFutureExt;
+use rbdc::Error;
+use rbdc::db::{Connection, ExecResult, Row};
+use rbs::Value;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+/// Queues commands on the session of an [`OracleConnection`] from several
+/// tasks at once, see [`OracleConnection::handle`].
+///
+/// Commands of all handles are served one at a time in the order they were
+/// queued. A query holds the session until its rows are consumed, so streams
+/// from [`Connection::exec_rows`] should not be kept open longer than needed.
+///
+/// Each handle, and each clone of one, owns the transactions it begins: while
+/// one is open, statements and `close` from any other handle or the connection
+/// itself fail instead of running inside it. Dropping or closing a handle
+/// rolls back a transaction it still holds.
+///
+/// When the connection re-establishes its session, automatically or with
+/// [`OracleConnection::reconnect`], handles move to the new session with
+/// their next command. A handle whose transaction was left behind on the old
+/// session fails that command with an error instead. Handles keep
+/// the session they use open until they are dropped.
+pub struct OracleConnectionHandle {
+    worker: ConnectionWorker,
+    /// The connection's current session.
+    current: Arc<Mutex<WeakWorker>>,
+    row_channel_size: usize,
+}
+
+/// Returned once by a handle whose open transaction stayed behind when the
+/// connection moved to a new session.
+pub(crate) const TRANSACTION_LOST: &str =
+    "the connection was reconnected, the open transaction of this handle is lost";
+
+impl OracleConnection {
+    /// A cloneable handle queueing commands on this connection's session.
+    pub fn handle(&self) -> OracleConnectionHandle {
+        OracleConnectionHandle {
+            worker: self.worker.handle(),
+            current: Arc::clone(&self.current),
+            row_channel_size: self.row_channel_size,
+        }
+    }
+}
+
+impl OracleConnectionHandle {
+    /// Whether this handle began the session's open transaction.
+    pub fn holds_transaction(&self) -> bool {
+        self.worker.holds_transaction()
+    }
+
+    /// The worker of the connection's current session.
+    fn worker(&mut self) -> Result<&mut ConnectionWorker, Error> {
+        let current = self.current.lock().unwrap().clone();
+        if !current.is_session_of(&self.worker)
+            && let Some(worker) = current.upgrade()
+        {
+            let lost = self.worker.holds_transaction();
+            self.worker = worker;
+            if lost {
+                return Err(Error::from(TRANSACTION_LOST));
+            }
+        }
+        Ok(&mut self.worker)
+    }
+
+    pub fn fetch_many(
+        &mut self,
+        query: OracleQuery,
+    ) -> BoxStream<'_, Result<Either<OracleQueryResult, OracleRow>, Error>> {
+        let sql = query.sql().to_owned();
+        let persistent = query.persistent() && !query.arguments.is_empty();
+        let row_channel_size = self.row_channel_size;
+        Box::pin(
+            async move {
+                let arguments = query.take_arguments()?;
+                let rx = self
+                    .worker()?
+                    .execute(sql, arguments, row_channel_size, persistent, None)
+                    .await?;
+                Ok::<_, Error>(unchunk(rx))
+            }
+            .try_flatten_stream(),
+        )
+    }
+}
+
+impl Clone for OracleConnectionHandle {
+    /// A handle for the same session that owns its own transactions.
+    fn clone(&self) -> Self {
+        Self {
+            worker: self.worker.handle(),
+            current: Arc::clone(&self.current),
+            row_channel_size: self.row_channel_size,
+        }
+    }
+}
+
+impl Debug for OracleConnectionHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OracleConnectionHandle")
+            .field("host", &self.worker.host)
+            .field("holds_transaction", &self.holds_transaction())
+            .finish()
+    }
+}
+
+impl Connection for OracleConnectionHandle {
+    fn exec_rows(
+        &mut self,
+        sql: &str,
+        params: Vec<Value>,
+    ) -> BoxFuture<'_, Result<BoxStream<'_, Result<Box<dyn Row>, Error>>, Error>> {
+        let sql = crate::OracleDriver.pub_exchange(sql);
+        Box::pin(async move {
+            let row_channel_size = self.row_channel_size;
+            exec_rows(self.worker()?, row_channel_size, sql, params).await
+        })
+    }
+
+    fn exec(&mut self, sql: &str, params: Vec<Value>) -> BoxFuture<'_, Result<ExecResult, Error>> {
+        let sql = crate::OracleDriver.pub_exchange(sql);
+        Box::pin(async move {
+            let row_channel_size = self.row_channel_size;
+            exec(self.worker()?, row_channel_size, sql, params).await
+        })
+    }
+
+    /// Rolls back a transaction this handle still holds. The session stays
+    /// open until the connection is closed.
+    fn close(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            if let Err(e) = self.worker() {
+                log::warn!("closing a handle: {e}");
+            }
+            if self.holds_transaction() {
+                log::warn!("closing a handle with an open transaction, rolling back");
+                self.rollback().await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move { self.worker()?.ping().await })
+    }
+}
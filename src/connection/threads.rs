@@ -0,0 +1,205 @@
+use rbdc::Error;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    /// The shared threads the current thread belongs to, see [`leave_pool`].
+    static POOL: RefCell<Option<Arc<SharedThreads>>> = const { RefCell::new(None) };
+}
+
+/// How the blocking work of connections is run: on one dedicated thread per
+/// connection, or on a bounded set of threads shared by all connections with
+/// the same settings.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct ThreadSettings {
+    /// `None` for a dedicated thread per connection.
+    pub(crate) shared_threads: Option<usize>,
+    pub(crate) stack_size: Option<usize>,
+    pub(crate) name_prefix: String,
+}
+
+impl ThreadSettings {
+    pub(crate) fn spawn(
+        &self,
+        name: String,
+        f: impl FnOnce() + Send + 'static,
+    ) -> Result<(), Error> {
+        let mut builder = thread::Builder::new().name(name);
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        builder
+            .spawn(f)
+            .map(|_| ())
+            .map_err(|e| Error::from(e.to_string()))
+    }
+}
+
+/// Fixed number of threads running jobs from a queue. Lives as long as the
+/// process once created through [`SharedThreads::get`].
+pub(crate) struct SharedThreads {
+    jobs: Sender<Job>,
+    queue: Mutex<Receiver<Job>>,
+    settings: ThreadSettings,
+    /// Names the next thread started, including replacements.
+    next_index: AtomicUsize,
+}
+
+static SHARED: Mutex<Vec<(ThreadSettings, Arc<SharedThreads>)>> = Mutex::new(Vec::new());
+
+impl SharedThreads {
+    /// The threads for `settings`, started on first use.
+    pub(crate) fn get(settings: &ThreadSettings) -> Result<Arc<Self>, Error> {
+        let mut shared = SHARED.lock().unwrap();
+        if let Some((_, threads)) = shared.iter().find(|(s, _)| s == settings) {
+            return Ok(Arc::clone(threads));
+        }
+
+        let count = settings.shared_threads.unwrap_or(1);
+        if count == 0 {
+            return Err(Error::from("worker_threads must be at least 1"));
+        }
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let threads = Arc::new(Self {
+            jobs,
+            queue: Mutex::new(queue),
+            settings: settings.clone(),
+            next_index: AtomicUsize::new(0),
+        });
+        for _ in 0..count {
+            threads.start_thread()?;
+        }
+
+        shared.push((settings.clone(), Arc::clone(&threads)));
+        Ok(threads)
+    }
+
+    pub(crate) fn run(&self, job: impl FnOnce() + Send + 'static) {
+        // the receiving threads never exit while `self` is alive
+        let _ = self.jobs.send(Box::new(job));
+    }
+
+    fn start_thread(self: &Arc<Self>) -> Result<(), Error> {
+        let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+        let threads = Arc::clone(self);
+        self.settings.spawn(
+            format!("{}-{index}", self.settings.name_prefix),
+            move || threads.run_jobs(),
+        )
+    }
+
+    fn run_jobs(self: Arc<Self>) {
+        POOL.with(|pool| *pool.borrow_mut() = Some(Arc::clone(&self)));
+        loop {
+            let job = match self.queue.lock().unwrap().recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            };
+            job();
+            if POOL.with(|pool| pool.borrow().is_none()) {
+                // replaced by `leave_pool` while running the job
+                return;
+            }
+        }
+    }
+}
+
+/// Called before the current job blocks for as long as someone else decides,
+/// such as a query waiting for its consumer: a new thread takes this one's
+/// place in its [`SharedThreads`], so the jobs of other connections keep
+/// running, and this thread exits once the job is done. Does nothing on other
+/// threads.
+pub(crate) fn leave_pool() {
+    let Some(threads) = POOL.with(|pool| pool.borrow_mut().take()) else {
+        return;
+    };
+    if let Err(e) = threads.start_thread() {
+        log::warn!("no thread can take the place of a blocked worker thread: {e}");
+        POOL.with(|pool| *pool.borrow_mut() = Some(threads));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SharedThreads, ThreadSettings, leave_pool};
+    use std::collections::HashSet;
+    use std::sync::{Arc, Barrier, mpsc};
+
+    fn settings(name_prefix: &str) -> ThreadSettings {
+        ThreadSettings {
+            shared_threads: Some(2),
+            stack_size: Some(256 * 1024),
+            name_prefix: name_prefix.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_jobs_share_named_threads() {
+        let threads = SharedThreads::get(&settings("test-shared")).expect("threads");
+        let (tx, rx) = mpsc::channel();
+        // both threads have to pick up a job before either finishes
+        let barrier = Arc::new(Barrier::new(2));
+        for _ in 0..2 {
+            let tx = tx.clone();
+            let barrier = Arc::clone(&barrier);
+            threads.run(move || {
+                barrier.wait();
+                tx.send(std::thread::current().name().map(str::to_owned))
+                    .unwrap();
+            });
+        }
+
+        let names: HashSet<_> = rx.iter().take(2).flatten().collect();
+        assert_eq!(
+            names,
+            HashSet::from(["test-shared-0".to_owned(), "test-shared-1".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_blocked_thread_replaced() {
+        let mut settings = settings("test-leave");
+        settings.shared_threads = Some(1);
+        let threads = SharedThreads::get(&settings).expect("threads");
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (tx, rx) = mpsc::channel();
+        let blocked = tx.clone();
+        threads.run(move || {
+            leave_pool();
+            release_rx.recv().unwrap();
+            blocked
+                .send(std::thread::current().name().map(str::to_owned))
+                .unwrap();
+        });
+        threads.run(move || {
+            tx.send(std::thread::current().name().map(str::to_owned))
+                .unwrap();
+        });
+
+        // runs while the only original thread is still blocked
+        assert_eq!(rx.recv().unwrap().as_deref(), Some("test-leave-1"));
+        release_tx.send(()).unwrap();
+        assert_eq!(rx.recv().unwrap().as_deref(), Some("test-leave-0"));
+    }
+
+    #[test]
+    fn test_same_settings_same_threads() {
+        let a = SharedThreads::get(&settings("test-reuse")).expect("threads");
+        let b = SharedThreads::get(&settings("test-reuse")).expect("threads");
+        let c = SharedThreads::get(&settings("test-other")).expect("threads");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+
+    #[test]
+    fn test_zero_threads_rejected() {
+        let mut settings = settings("test-zero");
+        settings.shared_threads = Some(0);
+        assert!(SharedThreads::get(&settings).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+/// Completes after `duration` on any executor. With the `tokio` feature the
+/// runtime's timer is used when called within a tokio runtime.
+#[cfg(feature = "tokio")]
+pub(crate) async fn sleep(duration: Duration) {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::time::sleep(duration).await
+    } else {
+        futures_timer::Delay::new(duration).await
+    }
+}
+
+/// Completes after `duration` on any executor, driven by a timer thread of
+/// its own.
+#[cfg(not(feature = "tokio"))]
+pub(crate) async fn sleep(duration: Duration) {
+    futures_timer::Delay::new(duration).await
+}
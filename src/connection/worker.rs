@@ -1,158 +1,386 @@
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::thread;
+use std::iter;
+use std::mem;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Instant;
 
 use crate::connection::ConnectionState;
-use crate::connection::establish::EstablishParams;
+use crate::connection::establish::{CONNECT_TIMEOUT, EstablishParams};
 use crate::connection::execute;
+use crate::connection::info::{SessionInfo, alters_session};
+use crate::connection::threads::{SharedThreads, leave_pool};
+use crate::connection::timer;
+use crate::error::is_dead_session;
+use crate::options::{CloseBehavior, SecretString};
 use crate::{OracleArguments, OracleQueryResult, OracleRow, OracleStatement};
-use crossfire::{AsyncTx, spsc};
+use crossfire::{MAsyncTx, TrySendError, mpsc, spsc};
 use either::Either;
 use futures_channel::oneshot;
+use futures_util::future;
 use rbdc::Error;
 use std::sync::Mutex;
 
+/// Submits commands to the session. Every `ConnectionWorker` of a session,
+/// see [`ConnectionWorker::handle`], is a separate transaction owner.
 pub(crate) struct ConnectionWorker {
-    command_tx: AsyncTx<crossfire::spsc::Array<Command>>,
+    sender: Arc<CommandSender>,
     pub(crate) shared: Arc<WorkerSharedState>,
+    /// Address the session was established with.
+    pub(crate) host: String,
+    owner: u64,
 }
 
+/// Finds the session of a `ConnectionWorker` without keeping it open, see
+/// [`ConnectionWorker::downgrade`].
+#[derive(Clone)]
+pub(crate) struct WeakWorker {
+    sender: Weak<CommandSender>,
+    shared: Arc<WorkerSharedState>,
+    host: String,
+}
+
+/// The sending side of a session's command queue, shared by all its
+/// `ConnectionWorker`s. The session ends when the last one is gone.
+struct CommandSender {
+    tx: MAsyncTx<mpsc::Array<Envelope>>,
+    /// Set when running on shared threads.
+    scheduler: Option<Arc<Scheduler>>,
+}
+
+/// A command and the `ConnectionWorker` that sent it.
+pub(crate) struct Envelope {
+    owner: u64,
+    command: Command,
+    /// Nobody waits for the reply, so the command is never cancelled.
+    detached: bool,
+}
+
+/// `transaction_owner` while no transaction is open.
+const NO_OWNER: u64 = 0;
+
+static NEXT_OWNER: AtomicU64 = AtomicU64::new(NO_OWNER + 1);
+
+/// Error returned for commands that would run inside another handle's
+/// transaction.
+pub(crate) const TRANSACTION_HELD: &str =
+    "the session has an open transaction owned by another handle";
+
 pub(crate) struct WorkerSharedState {
     pub(crate) cached_statements_size: AtomicUsize,
-    pub(crate) conn: Mutex<ConnectionState>,
+    /// Mirrors `ConnectionState::transaction_active` after every command.
+    pub(crate) transaction_active: AtomicBool,
+    /// Set once a command failed because the session is gone; the worker has
+    /// stopped by then.
+    pub(crate) broken: AtomicBool,
+    /// Set once the session was closed with `close`.
+    pub(crate) closed: AtomicBool,
+    /// The `ConnectionWorker` whose commands opened the active transaction.
+    pub(crate) transaction_owner: AtomicU64,
+    /// Read on the first `info()`; kept for a broken session.
+    pub(crate) session: Mutex<Option<SessionInfo>>,
+    /// Set by `ALTER SESSION`, so the next `info()` reads `session` again.
+    pub(crate) session_stale: AtomicBool,
+    pub(crate) last_used: Mutex<Instant>,
+}
+
+/// What serves the commands of one connection on the worker side; a session
+/// in production, a fake in tests.
+pub(crate) trait Backend: Send + 'static {
+    /// Runs one command. `Break` means no further commands can be served.
+    fn handle(&mut self, shared: &WorkerSharedState, cmd: Command) -> ControlFlow<()>;
+
+    /// The `ConnectionWorker` was dropped without `close`.
+    fn dropped(&mut self);
+
+    /// Nobody waited for the connection to be established.
+    fn abandon(&mut self);
+}
+
+/// The server session behind a connection.
+struct Session {
+    conn: ConnectionState,
+    close_behavior: CloseBehavior,
+    row_chunk_size: usize,
+}
+
+impl Backend for Session {
+    fn handle(&mut self, shared: &WorkerSharedState, cmd: Command) -> ControlFlow<()> {
+        handle_command(shared, self, cmd)
+    }
+
+    fn dropped(&mut self) {
+        end_dropped(&mut self.conn, self.close_behavior);
+    }
+
+    fn abandon(&mut self) {
+        let _ = self.conn.handle.connection().close();
+    }
+}
+
+/// A message on a query's row channel: the result of one statement, or up to
+/// `row_chunk_size` of its rows.
+pub(crate) type RowChunk = Result<Either<OracleQueryResult, Vec<OracleRow>>, Error>;
+
+/// Error returned for commands sent to a broken connection.
+pub(crate) const BROKEN: &str = "connection is broken, reconnect or drop it";
+
+/// End-to-end tracing attribute of a session. Values are piggybacked on the
+/// next round trip instead of costing one of their own.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SessionAttribute {
+    Module,
+    Action,
+    ClientIdentifier,
+    ClientInfo,
+    DbOp,
 }
 
+impl SessionAttribute {
+    pub(crate) fn apply(self, conn: &oracle::Connection, value: &str) -> Result<(), Error> {
+        match self {
+            SessionAttribute::Module => conn.set_module(value),
+            SessionAttribute::Action => conn.set_action(value),
+            SessionAttribute::ClientIdentifier => conn.set_client_identifier(value),
+            SessionAttribute::ClientInfo => conn.set_client_info(value),
+            SessionAttribute::DbOp => conn.set_db_op(value),
+        }
+        .map_err(|e| Error::from(e.to_string()))
+    }
+}
+
+/// Requests queued for the worker thread. New variants and fields may be
+/// added in minor releases.
+#[non_exhaustive]
 pub enum Command {
     Prepare {
         query: Box<str>,
         tx: oneshot::Sender<Result<OracleStatement, Error>>,
     },
+    #[non_exhaustive]
     Execute {
         query: Box<str>,
         arguments: Option<OracleArguments>,
         persistent: bool,
-        tx: crossfire::Tx<
-            crossfire::spsc::Array<Result<Either<OracleQueryResult, OracleRow>, Error>>,
-        >,
+        /// Fetch at most this many rows of each statement, then close its cursor.
+        row_limit: Option<u32>,
+        tx: crossfire::Tx<crossfire::spsc::Array<RowChunk>>,
     },
     ClearCache {
         tx: oneshot::Sender<()>,
     },
+    SetAttribute {
+        attribute: SessionAttribute,
+        value: String,
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
+    ChangePassword {
+        old_password: SecretString,
+        new_password: SecretString,
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
+    /// Reads the session details into the shared state.
+    LoadSessionInfo {
+        tx: oneshot::Sender<Result<(), Error>>,
+    },
     Ping {
         tx: oneshot::Sender<Result<(), Error>>,
     },
     Shutdown {
-        tx: oneshot::Sender<()>,
+        tx: oneshot::Sender<Result<(), Error>>,
     },
 }
 
-impl ConnectionWorker {
-    pub(crate) async fn establish(params: EstablishParams) -> Result<Self, Error> {
-        let (establish_tx, establish_rx) = oneshot::channel();
+impl Command {
+    /// Whether the caller's future was dropped while the command was queued.
+    /// The reply channel serves as the cancellation token: its receiver lives
+    /// exactly as long as someone waits for the result.
+    fn is_cancelled(&self) -> bool {
+        match self {
+            Command::Prepare { tx, .. } => tx.is_canceled(),
+            Command::Execute { tx, .. } => tx.is_disconnected(),
+            Command::ClearCache { tx } => tx.is_canceled(),
+            Command::SetAttribute { tx, .. }
+            | Command::ChangePassword { tx, .. }
+            | Command::LoadSessionInfo { tx }
+            | Command::Ping { tx }
+            | Command::Shutdown { tx } => tx.is_canceled(),
+        }
+    }
 
-        thread::Builder::new()
-            .name(params.thread_name.clone())
-            .spawn(move || {
-                let (command_tx, command_rx) =
-                    spsc::bounded_async_blocking(params.command_channel_size);
+    fn describe(&self) -> String {
+        match self {
+            Command::Prepare { query, .. } => format!("prepare of `{query}`"),
+            Command::Execute { query, .. } => format!("execution of `{query}`"),
+            Command::ClearCache { .. } => "statement cache clear".to_owned(),
+            Command::SetAttribute { attribute, .. } => format!("{attribute:?} update"),
+            Command::ChangePassword { .. } => "password change".to_owned(),
+            Command::LoadSessionInfo { .. } => "session info query".to_owned(),
+            Command::Ping { .. } => "ping".to_owned(),
+            Command::Shutdown { .. } => "close".to_owned(),
+        }
+    }
 
-                let conn = match params.establish() {
-                    Ok(conn) => conn,
-                    Err(e) => {
-                        establish_tx.send(Err(e)).ok();
-                        return;
-                    }
-                };
+    /// Commands that run inside, or end, the session's transaction.
+    fn is_transactional(&self) -> bool {
+        matches!(
+            self,
+            Command::Execute { .. } | Command::ChangePassword { .. } | Command::Shutdown { .. }
+        )
+    }
 
-                let shared = Arc::new(WorkerSharedState {
-                    cached_statements_size: AtomicUsize::new(0),
-                    conn: Mutex::new(conn),
-                });
-                let mut conn = shared.conn.lock().unwrap();
+    /// Fails the command without running it.
+    fn reject(self, error: Error) {
+        match self {
+            Command::Prepare { tx, .. } => {
+                tx.send(Err(error)).ok();
+            }
+            Command::Execute { tx, .. } => {
+                tx.send(Err(error)).ok();
+            }
+            Command::ClearCache { tx } => {
+                tx.send(()).ok();
+            }
+            Command::SetAttribute { tx, .. }
+            | Command::ChangePassword { tx, .. }
+            | Command::LoadSessionInfo { tx }
+            | Command::Ping { tx }
+            | Command::Shutdown { tx } => {
+                tx.send(Err(error)).ok();
+            }
+        }
+    }
+}
 
-                if establish_tx
-                    .send(Ok(Self {
-                        command_tx,
-                        shared: Arc::clone(&shared),
-                    }))
-                    .is_err()
-                {
-                    return;
-                }
+impl ConnectionWorker {
+    pub(crate) async fn establish(params: EstablishParams) -> Result<Self, Error> {
+        Self::establish_with(params, start_session).await
+    }
 
-                loop {
-                    let cmd = match command_rx.recv() {
-                        Ok(cmd) => cmd,
-                        Err(_) => break,
-                    };
+    /// Establishes a connection served by the backend `start` returns, which
+    /// runs on the worker thread.
+    pub(crate) async fn establish_with<B, F>(
+        params: EstablishParams,
+        start: F,
+    ) -> Result<Self, Error>
+    where
+        B: Backend,
+        F: FnOnce(&EstablishParams) -> Result<B, Error> + Send + 'static,
+    {
+        let (establish_tx, establish_rx) = oneshot::channel();
+        let connect_timeout = params.connect_timeout;
 
-                    match cmd {
-                        Command::Prepare { query, tx } => {
-                            tx.send(prepare(&mut conn, &query).map(|prepared| {
-                                update_cached_statements_size(
-                                    &conn,
-                                    &shared.cached_statements_size,
-                                );
-                                prepared
-                            }))
-                            .ok();
+        match params.threads.shared_threads {
+            None => params
+                .threads
+                .clone()
+                .spawn(params.thread_name.clone(), move || {
+                    let (command_tx, command_rx) =
+                        mpsc::bounded_async_blocking(params.command_channel_size);
+                    let mut backend = match start(&params) {
+                        Ok(started) => started,
+                        Err(e) => {
+                            establish_tx.send(Err(e)).ok();
+                            return;
                         }
-                        Command::Execute {
-                            query,
-                            arguments,
-                            persistent,
-                            tx,
-                        } => {
-                            let iter = match execute::iter(&mut conn, &query, arguments, persistent)
-                            {
-                                Ok(iter) => iter,
-                                Err(e) => {
-                                    tx.send(Err(e)).ok();
-                                    continue;
-                                }
-                            };
-
-                            for res in iter {
-                                if tx.send(res).is_err() {
-                                    break;
-                                }
-                            }
+                    };
+                    let shared = WorkerSharedState::new();
 
-                            update_cached_statements_size(&conn, &shared.cached_statements_size);
-                        }
-                        Command::ClearCache { tx } => {
-                            conn.statements.clear();
-                            update_cached_statements_size(&conn, &shared.cached_statements_size);
-                            tx.send(()).ok();
-                        }
-                        Command::Ping { tx } => {
-                            let result = conn
-                                .handle
-                                .connection()
-                                .ping()
-                                .map_err(|e| Error::from(e.to_string()));
-                            let should_stop = result.is_err();
-                            tx.send(result).ok();
-                            if should_stop {
-                                return;
+                    if establish_tx
+                        .send(Ok(Self::new(
+                            command_tx,
+                            None,
+                            Arc::clone(&shared),
+                            params.host().to_owned(),
+                        )))
+                        .is_err()
+                    {
+                        // the caller timed out or was dropped while connecting
+                        log::debug!("connect abandoned, closing the new session");
+                        backend.abandon();
+                        return;
+                    }
+
+                    loop {
+                        let flow = match command_rx.recv() {
+                            Ok(envelope) => dispatch(&mut backend, &shared, envelope),
+                            Err(_) => {
+                                backend.dropped();
+                                ControlFlow::Break(())
                             }
+                        };
+                        if flow.is_break() {
+                            return;
                         }
-                        Command::Shutdown { tx } => {
-                            let _ = conn.handle.connection().commit();
-                            let _ = conn.handle.connection().close();
-                            drop(conn);
-                            drop(shared);
-                            let _ = tx.send(());
+                    }
+                })?,
+            Some(_) => {
+                let threads = SharedThreads::get(&params.threads)?;
+                let pool = Arc::clone(&threads);
+                threads.run(move || {
+                    // a hung logon abandoned by `connect_timeout` must not hold
+                    // on to a shared thread
+                    leave_pool();
+                    let (command_tx, command_rx) =
+                        mpsc::bounded_async_blocking(params.command_channel_size);
+                    let backend = match start(&params) {
+                        Ok(started) => started,
+                        Err(e) => {
+                            establish_tx.send(Err(e)).ok();
                             return;
                         }
+                    };
+                    let shared = WorkerSharedState::new();
+                    let scheduler = Arc::new(Scheduler {
+                        threads: pool,
+                        shared: Arc::clone(&shared),
+                        command_rx: Mutex::new(Some(command_rx)),
+                        backend: Mutex::new(Box::new(backend)),
+                        scheduled: AtomicBool::new(false),
+                        dropped: AtomicBool::new(false),
+                    });
+
+                    if let Err(Ok(worker)) = establish_tx.send(Ok(Self::new(
+                        command_tx,
+                        Some(Arc::clone(&scheduler)),
+                        shared,
+                        params.host().to_owned(),
+                    ))) {
+                        log::debug!("connect abandoned, closing the new session");
+                        scheduler.backend.lock().unwrap().abandon();
+                        *scheduler.command_rx.lock().unwrap() = None;
+                        drop(worker);
+                    }
+                });
+            }
+        }
+
+        let established = match connect_timeout {
+            None => establish_rx.await,
+            Some(timeout) => {
+                match future::select(establish_rx, Box::pin(timer::sleep(timeout))).await {
+                    future::Either::Left((established, _)) => established,
+                    future::Either::Right(_) => {
+                        return Err(Error::from(format!("{CONNECT_TIMEOUT} after {timeout:?}")));
                     }
                 }
-            })
-            .map_err(|e| Error::from(e.to_string()))?;
+            }
+        };
+        established.map_err(|_| Error::from("WorkerCrashed"))?
+    }
 
-        establish_rx
-            .await
-            .map_err(|_| Error::from("WorkerCrashed"))?
+    fn new(
+        tx: MAsyncTx<mpsc::Array<Envelope>>,
+        scheduler: Option<Arc<Scheduler>>,
+        shared: Arc<WorkerSharedState>,
+        host: String,
+    ) -> Self {
+        Self {
+            sender: Arc::new(CommandSender { tx, scheduler }),
+            shared,
+            host,
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+        }
     }
 
     pub(crate) async fn prepare(&mut self, query: &str) -> Result<OracleStatement, Error> {
@@ -169,27 +397,62 @@ impl ConnectionWorker {
         args: Option<OracleArguments>,
         chan_size: usize,
         persistent: bool,
-    ) -> Result<
-        crossfire::AsyncRx<
-            crossfire::spsc::Array<Result<Either<OracleQueryResult, OracleRow>, Error>>,
-        >,
-        Error,
-    > {
+        row_limit: Option<u32>,
+    ) -> Result<crossfire::AsyncRx<crossfire::spsc::Array<RowChunk>>, Error> {
         let (tx, rx) = spsc::bounded_blocking_async(chan_size);
 
-        self.command_tx
-            .send(Command::Execute {
-                query: query.into(),
-                arguments: args.map(OracleArguments::into_static),
-                persistent,
-                tx,
-            })
-            .await
-            .map_err(|_| Error::from("WorkerCrashed"))?;
+        self.send(Command::Execute {
+            query: query.into(),
+            arguments: args.map(OracleArguments::into_static),
+            persistent,
+            row_limit,
+            tx,
+        })
+        .await?;
 
         Ok(rx)
     }
 
+    pub(crate) async fn set_attribute(
+        &mut self,
+        attribute: SessionAttribute,
+        value: &str,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::SetAttribute {
+            attribute,
+            value: value.to_owned(),
+            tx,
+        })
+        .await?
+    }
+
+    pub(crate) async fn change_password(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), Error> {
+        self.oneshot_cmd(|tx| Command::ChangePassword {
+            old_password: old_password.into(),
+            new_password: new_password.into(),
+            tx,
+        })
+        .await?
+    }
+
+    /// The session details, read from the server unless already known.
+    /// The session details, read from the server when missing or stale. A
+    /// broken session or a failed read leaves the last known ones, if any.
+    pub(crate) async fn session_info(&mut self) -> Option<SessionInfo> {
+        let loaded = self.shared.session.lock().unwrap().is_some();
+        if (!loaded || self.shared.session_stale.load(Ordering::Acquire)) && !self.is_broken() {
+            let result = self.oneshot_cmd(|tx| Command::LoadSessionInfo { tx }).await;
+            if let Err(e) = result.and_then(|result| result) {
+                log::warn!("session info not available: {e}");
+            }
+        }
+        self.shared.session.lock().unwrap().clone()
+    }
+
     pub(crate) async fn ping(&mut self) -> Result<(), Error> {
         self.oneshot_cmd(|tx| Command::Ping { tx }).await?
     }
@@ -200,12 +463,68 @@ impl ConnectionWorker {
     {
         let (tx, rx) = oneshot::channel();
 
-        self.command_tx
-            .send(command(tx))
+        self.send(command(tx)).await?;
+
+        rx.await.map_err(|_| self.disconnected())
+    }
+
+    async fn send(&mut self, command: Command) -> Result<(), Error> {
+        if self.is_broken() {
+            return Err(Error::from(BROKEN));
+        }
+        self.sender
+            .tx
+            .send(Envelope {
+                owner: self.owner,
+                command,
+                detached: false,
+            })
             .await
-            .map_err(|_| Error::from("WorkerCrashed"))?;
+            .map_err(|_| self.disconnected())?;
+        self.sender.schedule();
+        Ok(())
+    }
+
+    fn disconnected(&self) -> Error {
+        if self.is_broken() {
+            Error::from(BROKEN)
+        } else if self.shared.closed.load(Ordering::Acquire) {
+            Error::from("connection is closed")
+        } else {
+            Error::from("WorkerCrashed")
+        }
+    }
+
+    /// Another submitter for the same session, owning its own transactions.
+    pub(crate) fn handle(&self) -> Self {
+        Self {
+            sender: Arc::clone(&self.sender),
+            shared: Arc::clone(&self.shared),
+            host: self.host.clone(),
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn downgrade(&self) -> WeakWorker {
+        WeakWorker {
+            sender: Arc::downgrade(&self.sender),
+            shared: Arc::clone(&self.shared),
+            host: self.host.clone(),
+        }
+    }
 
-        rx.await.map_err(|_| Error::from("WorkerCrashed"))
+    /// Whether the active transaction was opened by this worker's commands.
+    pub(crate) fn holds_transaction(&self) -> bool {
+        self.transaction_active()
+            && self.shared.transaction_owner.load(Ordering::Acquire) == self.owner
+    }
+
+    pub(crate) fn is_broken(&self) -> bool {
+        self.shared.broken.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn transaction_active(&self) -> bool {
+        self.shared.transaction_active.load(Ordering::Acquire)
     }
 
     pub(crate) async fn clear_cache(&mut self) -> Result<(), Error> {
@@ -213,14 +532,412 @@ impl ConnectionWorker {
     }
 
     pub(crate) async fn shutdown(&mut self) -> Result<(), Error> {
-        let (tx, rx) = oneshot::channel();
+        if self.is_broken() {
+            // the worker already closed what was left of the session
+            return Ok(());
+        }
+        self.oneshot_cmd(|tx| Command::Shutdown { tx }).await?
+    }
+}
 
-        self.command_tx
-            .send(Command::Shutdown { tx })
-            .await
-            .map_err(|_| Error::from("WorkerCrashed"))?;
+impl WeakWorker {
+    /// A new submitter for the session, see [`ConnectionWorker::handle`];
+    /// `None` once every `ConnectionWorker` of it is gone.
+    pub(crate) fn upgrade(&self) -> Option<ConnectionWorker> {
+        Some(ConnectionWorker {
+            sender: self.sender.upgrade()?,
+            shared: Arc::clone(&self.shared),
+            host: self.host.clone(),
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+        })
+    }
+
+    pub(crate) fn is_session_of(&self, worker: &ConnectionWorker) -> bool {
+        Arc::ptr_eq(&self.shared, &worker.shared)
+    }
+}
+
+impl WorkerSharedState {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            cached_statements_size: AtomicUsize::new(0),
+            transaction_active: AtomicBool::new(false),
+            broken: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            transaction_owner: AtomicU64::new(NO_OWNER),
+            session: Mutex::new(None),
+            session_stale: AtomicBool::new(false),
+            last_used: Mutex::new(Instant::now()),
+        })
+    }
+}
+
+fn start_session(params: &EstablishParams) -> Result<Session, Error> {
+    Ok(Session {
+        conn: params.establish()?,
+        close_behavior: params.close_behavior,
+        row_chunk_size: params.row_chunk_size,
+    })
+}
 
-        rx.await.map_err(|_| Error::from("WorkerCrashed"))
+/// Runs one command. `Break` means the session is closed or lost and no
+/// further commands can be served.
+fn handle_command(
+    shared: &WorkerSharedState,
+    session: &mut Session,
+    cmd: Command,
+) -> ControlFlow<()> {
+    let conn = &mut session.conn;
+    match cmd {
+        Command::Prepare { query, tx } => {
+            let result = prepare(conn, &query).inspect(|_| {
+                update_cached_statements_size(conn, &shared.cached_statements_size);
+            });
+            check_alive(shared, &result);
+            tx.send(result).ok();
+        }
+        Command::Execute {
+            query,
+            arguments,
+            persistent,
+            row_limit,
+            tx,
+        } => {
+            match execute::iter(conn, &query, arguments, persistent, row_limit) {
+                Ok(mut iter) => {
+                    let results = iter::from_fn(|| {
+                        let res = iter.next()?;
+                        check_alive(shared, &res);
+                        shared
+                            .transaction_active
+                            .store(iter.transaction_active(), Ordering::Release);
+                        Some(res)
+                    });
+                    send_chunked(&tx, session.row_chunk_size, results);
+                }
+                Err(e) => {
+                    let result = Err(e);
+                    check_alive(shared, &result);
+                    tx.send(result).ok();
+                }
+            }
+
+            update_cached_statements_size(conn, &shared.cached_statements_size);
+            shared
+                .transaction_active
+                .store(conn.transaction_active, Ordering::Release);
+            if alters_session(&query) {
+                // e.g. the current schema, read again by the next `info()`
+                shared.session_stale.store(true, Ordering::Release);
+            }
+        }
+        Command::ClearCache { tx } => {
+            conn.statements.clear();
+            update_cached_statements_size(conn, &shared.cached_statements_size);
+            tx.send(()).ok();
+        }
+        Command::SetAttribute {
+            attribute,
+            value,
+            tx,
+        } => {
+            let result = attribute.apply(conn.handle.connection(), &value);
+            check_alive(shared, &result);
+            tx.send(result).ok();
+        }
+        Command::ChangePassword {
+            old_password,
+            new_password,
+            tx,
+        } => {
+            let result = change_password(conn, &old_password, &new_password);
+            check_alive(shared, &result);
+            tx.send(result).ok();
+        }
+        Command::LoadSessionInfo { tx } => {
+            shared.session_stale.store(false, Ordering::Release);
+            let result = SessionInfo::query(conn.handle.connection())
+                .map(|session| *shared.session.lock().unwrap() = Some(session));
+            check_alive(shared, &result);
+            tx.send(result).ok();
+        }
+        Command::Ping { tx } => {
+            let result = conn
+                .handle
+                .connection()
+                .ping()
+                .map_err(|e| Error::from(e.to_string()));
+            check_alive(shared, &result);
+            tx.send(result).ok();
+        }
+        Command::Shutdown { tx } => {
+            if let Err(e) = end_transaction(conn, session.close_behavior) {
+                tx.send(Err(e)).ok();
+                return ControlFlow::Continue(());
+            }
+            let _ = conn.handle.connection().close();
+            conn.statements.clear();
+            let _ = tx.send(Ok(()));
+            return ControlFlow::Break(());
+        }
+    }
+
+    *shared.last_used.lock().unwrap() = Instant::now();
+    if shared.broken.load(Ordering::Acquire) {
+        log::warn!("session lost, stopping worker");
+        let _ = conn.handle.connection().close();
+        return ControlFlow::Break(());
+    }
+    ControlFlow::Continue(())
+}
+
+/// Sends `results` with their rows grouped into chunks of up to `chunk_size`,
+/// stopping early once the receiver is gone. Once the consumer falls behind,
+/// the rest of the query runs outside the shared threads, see [`leave_pool`].
+pub(crate) fn send_chunked(
+    tx: &crossfire::Tx<spsc::Array<RowChunk>>,
+    chunk_size: usize,
+    results: impl Iterator<Item = Result<Either<OracleQueryResult, OracleRow>, Error>>,
+) {
+    let mut rows = Vec::with_capacity(chunk_size);
+    for result in results {
+        let message = match result {
+            Ok(Either::Right(row)) => {
+                rows.push(row);
+                if rows.len() < chunk_size {
+                    continue;
+                }
+                Ok(Either::Right(mem::replace(
+                    &mut rows,
+                    Vec::with_capacity(chunk_size),
+                )))
+            }
+            other => {
+                // rows go out before the result or error following them
+                if !rows.is_empty() && !send_row_chunk(tx, Ok(Either::Right(mem::take(&mut rows))))
+                {
+                    return;
+                }
+                other.map(|step| step.map_right(|row| vec![row]))
+            }
+        };
+        if !send_row_chunk(tx, message) {
+            return;
+        }
+    }
+    if !rows.is_empty() {
+        send_row_chunk(tx, Ok(Either::Right(rows)));
+    }
+}
+
+/// Whether the receiver is still there to take `message`.
+fn send_row_chunk(tx: &crossfire::Tx<spsc::Array<RowChunk>>, message: RowChunk) -> bool {
+    match tx.try_send(message) {
+        Ok(()) => true,
+        Err(TrySendError::Full(message)) => {
+            leave_pool();
+            tx.send(message).is_ok()
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    }
+}
+
+/// The connection was dropped without `close`; there is nobody left to report
+/// an error to, so `CloseBehavior::Error` rolls back.
+pub(crate) fn end_dropped(conn: &mut ConnectionState, close_behavior: CloseBehavior) {
+    let behavior = match close_behavior {
+        CloseBehavior::Error => CloseBehavior::Rollback,
+        behavior => behavior,
+    };
+    if let Err(e) = end_transaction(conn, behavior) {
+        log::warn!("ending the open transaction failed: {e}");
+    }
+}
+
+/// Runs the commands of one connection on [`SharedThreads`]: whenever commands
+/// are queued, one job drains them, so a connection occupies a thread only
+/// while it has work.
+struct Scheduler {
+    threads: Arc<SharedThreads>,
+    shared: Arc<WorkerSharedState>,
+    /// `None` once the session is closed, failing further sends.
+    command_rx: Mutex<Option<crossfire::Rx<mpsc::Array<Envelope>>>>,
+    backend: Mutex<Box<dyn Backend>>,
+    scheduled: AtomicBool,
+    /// The `ConnectionWorker` is gone.
+    dropped: AtomicBool,
+}
+
+impl Scheduler {
+    fn schedule(self: &Arc<Self>) {
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            let scheduler = Arc::clone(self);
+            self.threads.run(move || scheduler.drain());
+        }
+    }
+
+    fn drain(self: Arc<Self>) {
+        loop {
+            let dropped = self.dropped.load(Ordering::Acquire);
+            {
+                let mut command_rx = self.command_rx.lock().unwrap();
+                if let Some(rx) = command_rx.as_ref() {
+                    let mut backend = self.backend.lock().unwrap();
+                    let mut flow = ControlFlow::Continue(());
+                    while let Ok(envelope) = rx.try_recv() {
+                        flow = dispatch(&mut **backend, &self.shared, envelope);
+                        if flow.is_break() {
+                            break;
+                        }
+                    }
+                    if flow.is_continue() && dropped {
+                        backend.dropped();
+                        flow = ControlFlow::Break(());
+                    }
+                    if flow.is_break() {
+                        *command_rx = None;
+                    }
+                }
+            }
+
+            self.scheduled.store(false, Ordering::Release);
+            // commands queued after the last `try_recv` found no job scheduled
+            if !self.has_work() || self.scheduled.swap(true, Ordering::AcqRel) {
+                return;
+            }
+        }
+    }
+
+    fn has_work(&self) -> bool {
+        self.command_rx
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|rx| !rx.is_empty() || self.dropped.load(Ordering::Acquire))
+    }
+}
+
+impl CommandSender {
+    fn schedule(&self) {
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.schedule();
+        }
+    }
+}
+
+impl Drop for CommandSender {
+    fn drop(&mut self) {
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.dropped.store(true, Ordering::Release);
+            scheduler.schedule();
+        }
+    }
+}
+
+impl Drop for ConnectionWorker {
+    /// Rolls back a transaction this worker still holds while other handles
+    /// keep the session open; the last one ends it with `close_behavior`.
+    fn drop(&mut self) {
+        if !self.holds_transaction() || Arc::strong_count(&self.sender) == 1 {
+            return;
+        }
+        log::warn!("dropping a handle with an open transaction, rolling back");
+        let (tx, _rx) = spsc::bounded_blocking_async(1);
+        let rollback = Envelope {
+            owner: self.owner,
+            command: Command::Execute {
+                query: "rollback".into(),
+                arguments: None,
+                persistent: false,
+                row_limit: None,
+                tx,
+            },
+            detached: true,
+        };
+        if self.sender.tx.try_send(rollback).is_err() {
+            log::warn!("command queue full, the transaction stays open");
+        }
+        self.sender.schedule();
+    }
+}
+
+/// Runs a command unless it would interfere with another owner's
+/// transaction, then records who holds the transaction.
+fn dispatch(
+    backend: &mut dyn Backend,
+    shared: &WorkerSharedState,
+    envelope: Envelope,
+) -> ControlFlow<()> {
+    let Envelope {
+        owner,
+        command,
+        detached,
+    } = envelope;
+    if !detached && command.is_cancelled() {
+        log::warn!(
+            "skipping {}: its caller stopped waiting before it started",
+            command.describe()
+        );
+        return ControlFlow::Continue(());
+    }
+    let holder = shared.transaction_owner.load(Ordering::Acquire);
+    if holder != NO_OWNER && holder != owner && command.is_transactional() {
+        command.reject(Error::from(TRANSACTION_HELD));
+        return ControlFlow::Continue(());
+    }
+
+    // set before replies are sent, so the owner sees its transaction as soon
+    // as `begin` returns
+    if holder == NO_OWNER {
+        shared.transaction_owner.store(owner, Ordering::Release);
+    }
+    let shutdown = matches!(command, Command::Shutdown { .. });
+    let flow = backend.handle(shared, command);
+    if shutdown && flow.is_break() {
+        shared.closed.store(true, Ordering::Release);
+    }
+    if !shared.transaction_active.load(Ordering::Acquire) {
+        shared.transaction_owner.store(NO_OWNER, Ordering::Release);
+    }
+    flow
+}
+
+/// Ends a transaction still open when the session is about to be closed.
+pub(crate) fn end_transaction(
+    conn: &mut ConnectionState,
+    behavior: CloseBehavior,
+) -> Result<(), Error> {
+    if !conn.transaction_active {
+        return Ok(());
+    }
+    let connection = conn.handle.connection();
+    match behavior {
+        CloseBehavior::Rollback => {
+            log::warn!("closing a connection with an open transaction, rolling back");
+            connection.rollback()
+        }
+        CloseBehavior::Commit => {
+            log::warn!("closing a connection with an open transaction, committing");
+            connection.commit()
+        }
+        CloseBehavior::Error => {
+            log::warn!("refusing to close a connection with an open transaction");
+            return Err(Error::from(
+                "connection has an open transaction, commit or roll back before closing",
+            ));
+        }
+    }
+    .map_err(|e| Error::from(e.to_string()))?;
+    conn.transaction_active = false;
+    Ok(())
+}
+
+/// Marks the connection broken when `result` reports a lost session.
+fn check_alive<T>(shared: &WorkerSharedState, result: &Result<T, Error>) {
+    if let Err(e) = result
+        && is_dead_session(e)
+    {
+        shared.broken.store(true, Ordering::Release);
     }
 }
 
@@ -228,6 +945,88 @@ fn update_cached_statements_size(conn: &ConnectionState, size: &AtomicUsize) {
     size.store(conn.statements.len(), Ordering::Release);
 }
 
+fn change_password(
+    conn: &ConnectionState,
+    old_password: &SecretString,
+    new_password: &SecretString,
+) -> Result<(), Error> {
+    let connection = conn.handle.connection();
+    let username = connection
+        .query_row_as::<String>(
+            "SELECT SYS_CONTEXT('USERENV', 'SESSION_USER') FROM dual",
+            &[],
+        )
+        .map_err(|e| Error::from(e.to_string()))?;
+    connection
+        .change_password(
+            &username,
+            old_password.expose_secret(),
+            new_password.expose_secret(),
+        )
+        .map_err(|e| Error::from(e.to_string()))
+}
+
 fn prepare(conn: &mut ConnectionState, query: &str) -> Result<OracleStatement, Error> {
     super::executor::prepare(conn, query)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::send_chunked;
+    use crate::{OracleQueryResult, OracleRow};
+    use crossfire::spsc;
+    use either::Either;
+    use rbdc::Error;
+    use std::sync::Arc;
+
+    fn row() -> Result<Either<OracleQueryResult, OracleRow>, Error> {
+        Ok(Either::Right(OracleRow::new(Arc::default(), vec![])))
+    }
+
+    fn result() -> Result<Either<OracleQueryResult, OracleRow>, Error> {
+        Ok(Either::Left(OracleQueryResult::default()))
+    }
+
+    /// Rows per chunk, `0` for a statement result and `-1` for an error.
+    fn chunks(
+        chunk_size: usize,
+        results: Vec<Result<Either<OracleQueryResult, OracleRow>, Error>>,
+    ) -> Vec<isize> {
+        let (tx, rx) = spsc::bounded_blocking_async(16);
+        send_chunked(&tx, chunk_size, results.into_iter());
+        drop(tx);
+        let mut chunks = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            chunks.push(match message {
+                Ok(Either::Right(rows)) => rows.len() as isize,
+                Ok(Either::Left(_)) => 0,
+                Err(_) => -1,
+            });
+        }
+        chunks
+    }
+
+    #[test]
+    fn test_rows_are_chunked_per_statement() {
+        let results = vec![
+            row(),
+            row(),
+            row(),
+            row(),
+            row(),
+            result(),
+            row(),
+            row(),
+            result(),
+        ];
+        assert_eq!(chunks(2, results), vec![2, 2, 1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_rows_flushed_before_errors() {
+        let results = vec![row(), row(), row(), Err(Error::from("ORA-01722"))];
+        assert_eq!(chunks(64, results), vec![3, -1]);
+        assert_eq!(chunks(1, vec![row(), row()]), vec![1, 1]);
+        assert_eq!(chunks(8, vec![row(), row()]), vec![2]);
+    }
+}
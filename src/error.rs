@@ -37,3 +37,43 @@ impl From<OracleError> for rbdc::Error {
         Self::from(value.to_string())
     }
 }
+
+/// Whether an error message carries one of the given Oracle error codes,
+/// e.g. `"ORA-01017"` or `"DPI-1080"`.
+pub(crate) fn has_error_code(error: &rbdc::Error, codes: &[&str]) -> bool {
+    let message = error.to_string();
+    codes.iter().any(|code| message.contains(code))
+}
+
+/// The session is gone: end-of-file on the communication channel, not
+/// connected, connection lost contact, and ODPI's "connection was closed".
+pub(crate) const DEAD_SESSION_ERRORS: &[&str] = &[
+    "ORA-03113",
+    "ORA-03114",
+    "ORA-03135",
+    "DPI-1080",
+    "DPI-1010",
+];
+
+pub(crate) fn is_dead_session(error: &rbdc::Error) -> bool {
+    has_error_code(error, DEAD_SESSION_ERRORS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_dead_session;
+
+    #[test]
+    fn test_dead_session_errors() {
+        for message in [
+            "ORA-03113: end-of-file on communication channel",
+            "ORA-03135: connection lost contact",
+            "DPI-1080: connection was closed by ORA-3113",
+        ] {
+            assert!(is_dead_session(&rbdc::Error::from(message)), "{message}");
+        }
+        assert!(!is_dead_session(&rbdc::Error::from(
+            "ORA-00942: table or view does not exist"
+        )));
+    }
+}
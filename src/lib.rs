@@ -2,7 +2,7 @@
 
 pub use arguments::{OracleArgumentValue, OracleArguments};
 pub use column::OracleColumn;
-pub use connection::OracleConnection;
+pub use connection::{OracleConnection, OracleConnectionHandle};
 pub use database::Oracle;
 pub use driver::OracleDriver;
 pub use driver::OracleDriver as Driver;
@@ -12,10 +12,12 @@ pub use query::OracleQuery;
 pub use query_result::OracleQueryResult;
 pub use row::OracleRow;
 pub use statement::OracleStatement;
+pub use tnsnames::TnsNames;
 pub use type_info::OracleTypeInfo;
 pub use value::{OracleValue, OracleValueRef};
 
 pub mod arguments;
+pub mod blocking;
 pub mod column;
 pub mod connection;
 pub mod database;
@@ -28,6 +30,7 @@ pub mod query;
 pub mod query_result;
 pub mod row;
 pub mod statement;
+pub mod tnsnames;
 pub mod type_info;
 pub mod types;
 pub mod value;
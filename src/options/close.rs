@@ -0,0 +1,35 @@
+use rbdc::Error;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// What happens to an open transaction when its connection is closed or
+/// dropped.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseBehavior {
+    /// Discard the uncommitted work.
+    #[default]
+    Rollback,
+    Commit,
+    /// Refuse to close, leaving the transaction to the caller. A dropped
+    /// connection is rolled back since there is nobody to report to.
+    Error,
+}
+
+impl FromStr for CloseBehavior {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("rollback") {
+            Ok(Self::Rollback)
+        } else if value.eq_ignore_ascii_case("commit") {
+            Ok(Self::Commit)
+        } else if value.eq_ignore_ascii_case("error") {
+            Ok(Self::Error)
+        } else {
+            Err(Error::from(format!(
+                "expected rollback, commit or error, found `{value}`"
+            )))
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::options::SecretString;
+use futures_core::future::BoxFuture;
+use rbdc::Error;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+/// Username and password handed out by a [`CredentialProvider`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Credentials {
+    pub username: String,
+    pub password: SecretString,
+}
+
+impl Credentials {
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: SecretString::new(password),
+        }
+    }
+}
+
+/// Source of credentials for secrets that rotate, e.g. a vault.
+///
+/// `credentials` is awaited before every connection attempt and overrides
+/// `OracleConnectOptions::username` / `password`. When the server answers with
+/// ORA-01017 (invalid login) or ORA-28001 (password expired), `invalidate` is
+/// called and the attempt is retried once with freshly fetched credentials.
+pub trait CredentialProvider: Send + Sync {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>>;
+
+    /// The last credentials were rejected; the next `credentials` call should
+    /// bypass any cache.
+    fn invalidate(&self) {}
+}
+
+impl<T: CredentialProvider + ?Sized> CredentialProvider for Arc<T> {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+        (**self).credentials()
+    }
+
+    fn invalidate(&self) {
+        (**self).invalidate()
+    }
+}
+
+/// A [`CredentialProvider`] stored in `OracleConnectOptions`. Two values are
+/// equal when they share the same provider.
+#[derive(Clone)]
+pub struct SharedCredentialProvider(Arc<dyn CredentialProvider>);
+
+impl SharedCredentialProvider {
+    pub fn new(provider: impl CredentialProvider + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+
+    pub(crate) fn provider(&self) -> &dyn CredentialProvider {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for SharedCredentialProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("CredentialProvider")
+    }
+}
+
+impl PartialEq for SharedCredentialProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedCredentialProvider {}
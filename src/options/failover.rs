@@ -0,0 +1,144 @@
+use crate::OracleConnectOptions;
+use rbdc::Error;
+use url::Url;
+
+impl OracleConnectOptions {
+    /// One copy of these options per listener address, in the order they are
+    /// tried, each paired with its `host:port`. Without `hosts` this is just
+    /// `self`, labelled with its Easy Connect address or the connect string.
+    pub(crate) fn failover_targets(&self) -> Result<Vec<(String, OracleConnectOptions)>, Error> {
+        let Some(url) = self.easy_connect_url() else {
+            if !self.hosts.is_empty() {
+                return Err(Error::from(
+                    "hosts require an Easy Connect connect_string (//host:port/service)",
+                ));
+            }
+            return Ok(vec![(self.connect_string.clone(), self.clone())]);
+        };
+
+        let mut targets = vec![(self.address_label(&url), self.clone())];
+        for host in &self.hosts {
+            let (name, port) = split_host(host)?;
+            let mut url = url.clone();
+            url.set_host(Some(name))
+                .map_err(|e| Error::from(format!("Invalid host `{host}`: {e}")))?;
+            let _ = url.set_port(port);
+
+            let mut options = self.clone();
+            options.connect_string = url.as_str().trim_start_matches("oracle:").to_owned();
+            targets.push((self.address_label(&url), options));
+        }
+
+        if self.load_balance {
+            fastrand::shuffle(&mut targets);
+        }
+        Ok(targets)
+    }
+
+    fn address_label(&self, url: &Url) -> String {
+        format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port().unwrap_or(self.protocol.default_port())
+        )
+    }
+}
+
+/// Splits `host`, `host:port` or `[v6]:port`.
+fn split_host(host: &str) -> Result<(&str, Option<u16>), Error> {
+    let invalid = || Error::from(format!("Invalid host `{host}`, expected host[:port]"));
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port))
+            if !port.contains(']') && (!name.contains(':') || name.ends_with(']')) =>
+        {
+            (name, Some(port.parse().map_err(|_| invalid())?))
+        }
+        _ => (host, None),
+    };
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    Ok((name, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::OracleConnectOptions;
+    use crate::options::OracleProtocol;
+
+    fn hosts(options: &OracleConnectOptions) -> Vec<(String, String)> {
+        options
+            .failover_targets()
+            .expect("targets")
+            .into_iter()
+            .map(|(host, options)| (host, options.connect_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_single_target() {
+        let options = OracleConnectOptions::new().connect_string("//db/svc");
+        assert_eq!(
+            hosts(&options),
+            vec![("db:1521".to_owned(), "//db/svc".to_owned())]
+        );
+
+        let options = OracleConnectOptions::new().connect_string("PROD");
+        assert_eq!(
+            hosts(&options),
+            vec![("PROD".to_owned(), "PROD".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_failover_targets_in_order() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//scan.example.com:1521/svc:dedicated?retry_count=2")
+            .add_host("standby.example.com:1522")
+            .add_host("[::1]");
+
+        assert_eq!(
+            hosts(&options),
+            vec![
+                (
+                    "scan.example.com:1521".to_owned(),
+                    "//scan.example.com:1521/svc:dedicated?retry_count=2".to_owned()
+                ),
+                (
+                    "standby.example.com:1522".to_owned(),
+                    "//standby.example.com:1522/svc:dedicated?retry_count=2".to_owned()
+                ),
+                (
+                    "[::1]:1521".to_owned(),
+                    "//[::1]/svc:dedicated?retry_count=2".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_balance_keeps_all_targets() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//a/svc")
+            .protocol(OracleProtocol::Tcps)
+            .hosts(vec!["b".to_owned(), "c:2485".to_owned()])
+            .load_balance(true);
+
+        let mut targets: Vec<String> = hosts(&options).into_iter().map(|(host, _)| host).collect();
+        targets.sort();
+        assert_eq!(targets, vec!["a:2484", "b:2484", "c:2485"]);
+    }
+
+    #[test]
+    fn test_invalid_hosts() {
+        let options = OracleConnectOptions::new()
+            .connect_string("PROD")
+            .add_host("standby");
+        assert!(options.failover_targets().is_err());
+
+        let options = OracleConnectOptions::new()
+            .connect_string("//a/svc")
+            .add_host("b:port");
+        assert!(options.failover_targets().is_err());
+    }
+}
@@ -1,10 +1,28 @@
+mod close;
 mod connect;
+mod credentials;
+mod failover;
 mod parse;
+mod retry;
+mod secret;
+mod session;
+mod tls;
+mod token;
+mod uri;
+
+pub use close::CloseBehavior;
+pub use credentials::{CredentialProvider, Credentials, SharedCredentialProvider};
+pub use retry::ConnectRetry;
+pub use secret::SecretString;
+pub use tls::OracleProtocol;
+pub(crate) use tls::TcpsDescriptor;
+pub use token::{AccessToken, SharedTokenProvider, TokenProvider};
 
 use futures_core::future::BoxFuture;
 use rbdc::Error;
 use rbdc::db::{ConnectOptions, Connection};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Connection options for Oracle.
 ///
@@ -15,14 +33,148 @@ use serde::{Deserialize, Serialize};
 #[non_exhaustive]
 pub struct OracleConnectOptions {
     pub username: String,
-    pub password: String,
+    /// Redacted in `Debug` and `Serialize`; see
+    /// [`OracleConnectOptions::to_json_with_password`].
+    pub password: SecretString,
+    /// Replaces an expired password during logon (ORA-28001). Not part of the
+    /// URL form; see [`OracleConnectOptions::connect_with_new_password`].
+    #[serde(default)]
+    pub new_password: Option<SecretString>,
+    /// Fetches `username` / `password` at every connect instead.
+    #[serde(skip)]
+    pub credential_provider: Option<SharedCredentialProvider>,
+    /// Logs on with a token instead of `username` / `password`. Requires
+    /// protocol `tcps`.
+    #[serde(default)]
+    pub access_token: Option<AccessToken>,
+    /// Fetches the access token at every connect.
+    #[serde(skip)]
+    pub token_provider: Option<SharedTokenProvider>,
     pub connect_string: String,
+    /// Further listener addresses (`host[:port]`) serving the service of the
+    /// Easy Connect `connect_string`, e.g. a standby or other RAC nodes. They
+    /// are tried in order after the `connect_string` host when it is
+    /// unreachable.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Tries the `connect_string` host and `hosts` in random order instead.
+    #[serde(default)]
+    pub load_balance: bool,
     #[serde(default = "OracleConnectOptions::default_statement_cache_capacity")]
     pub statement_cache_capacity: usize,
+    /// Capacity of a query's row channel, in chunks of `row_chunk_size` rows.
     #[serde(default = "OracleConnectOptions::default_row_channel_size")]
     pub row_channel_size: usize,
+    /// Rows sent from the worker per channel message.
+    #[serde(default = "OracleConnectOptions::default_row_chunk_size")]
+    pub row_chunk_size: usize,
     #[serde(default = "OracleConnectOptions::default_command_channel_size")]
     pub command_channel_size: usize,
+    /// Gives up on a connection attempt that has not completed in time.
+    ///
+    /// Only the caller stops waiting: a logon hung in the network keeps its
+    /// worker thread until the client gives up on its own. Add
+    /// `transport_connect_timeout` to the Easy Connect string (or
+    /// `CONNECT_TIMEOUT` to `sqlnet.ora`) to bound that as well.
+    #[serde(default)]
+    pub connect_timeout: Option<Duration>,
+    /// Retries attempts that failed with a transient listener or service error.
+    #[serde(default)]
+    pub connect_retry: ConnectRetry,
+    /// Re-establishes a lost session on the next use instead of failing, as
+    /// long as no transaction was open.
+    #[serde(default)]
+    pub reconnect: bool,
+    /// Handling of a transaction still open when the connection is closed.
+    #[serde(default)]
+    pub close_behavior: CloseBehavior,
+    /// Runs all connections with the same thread settings on this many shared
+    /// threads instead of one dedicated thread per connection. A connection
+    /// holds a thread only while it executes commands, so size it for the
+    /// queries in flight, not for the pool. Logons, and queries whose rows
+    /// are not read fast enough, move to a thread of their own and a new
+    /// thread takes their place, so hung logons and open result streams never
+    /// starve other connections. The number is therefore a target for the
+    /// threads serving commands, not a limit on the threads alive.
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+    /// Stack size of the worker threads; the Rust default when unset.
+    #[serde(default)]
+    pub worker_stack_size: Option<usize>,
+    /// Worker threads are named `<prefix>-<n>`.
+    #[serde(default = "OracleConnectOptions::default_worker_thread_name")]
+    pub worker_thread_name: String,
+    /// `NLS_DATE_FORMAT` applied to every new session. Unset, the session keeps
+    /// the client's default.
+    ///
+    /// Date columns are fetched as native values and decode the same either
+    /// way; the format only governs text the server converts implicitly, such
+    /// as strings bound to `DATE` columns or `TO_CHAR` without a format. Use
+    /// `"YYYY-MM-DD HH24:MI:SS"` to match the text the driver returns.
+    #[serde(default)]
+    pub nls_date_format: Option<String>,
+    /// `NLS_TIMESTAMP_FORMAT` applied to every new session. Like
+    /// `nls_date_format` it only affects implicit conversions;
+    /// `"YYYY-MM-DD HH24:MI:SS.FF"` matches the decoded timestamps.
+    #[serde(default)]
+    pub nls_timestamp_format: Option<String>,
+    /// `NLS_NUMERIC_CHARACTERS` applied to every new session, e.g. `".,"`.
+    /// Unset, the session keeps the client's default.
+    ///
+    /// Numbers are fetched and decimals bound as native values with a `.`
+    /// either way; the setting only governs text the server converts
+    /// implicitly, such as strings bound to `NUMBER` columns or `TO_CHAR`
+    /// without a format. Use `".,"` to match the text the driver returns.
+    #[serde(default)]
+    pub nls_numeric_characters: Option<String>,
+    /// Session `TIME_ZONE`, e.g. `"+00:00"` or `"UTC"`.
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    /// `NLS_LANGUAGE` applied to every new session.
+    #[serde(default)]
+    pub nls_language: Option<String>,
+    /// `tcps` turns an Easy Connect `connect_string` into a TLS descriptor.
+    #[serde(default)]
+    pub protocol: OracleProtocol,
+    /// Whether the server certificate DN must match the service host.
+    #[serde(default)]
+    pub ssl_server_dn_match: Option<bool>,
+    /// Expected distinguished name of the server certificate. Values with
+    /// `"`, `(` or `)` are rejected at connect time.
+    #[serde(default)]
+    pub ssl_server_cert_dn: Option<String>,
+    /// Directory of the client wallet; `"`, `(` and `)` are rejected like in
+    /// `ssl_server_cert_dn`.
+    #[serde(default)]
+    pub wallet_location: Option<String>,
+    /// Directory holding `tnsnames.ora` and `sqlnet.ora`, like `TNS_ADMIN`.
+    ///
+    /// The Oracle client is initialized once per process, so only the value of
+    /// the first connection takes effect.
+    #[serde(default)]
+    pub config_dir: Option<String>,
+    /// Edition selected at logon, for edition-based redefinition.
+    #[serde(default)]
+    pub edition: Option<String>,
+    /// Name reported in `V$SESSION_CONNECT_INFO.CLIENT_DRIVER`.
+    #[serde(default = "OracleConnectOptions::default_driver_name")]
+    pub driver_name: String,
+    /// Initial `MODULE` of every new session.
+    #[serde(default)]
+    pub module: Option<String>,
+    /// Initial `ACTION` of every new session.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Initial `CLIENT_IDENTIFIER` of every new session.
+    #[serde(default)]
+    pub client_identifier: Option<String>,
+    /// Initial `CLIENT_INFO` of every new session.
+    #[serde(default)]
+    pub client_info: Option<String>,
+    /// SQL statements executed on every new connection before it is handed out,
+    /// e.g. `ALTER SESSION SET ...`.
+    #[serde(default)]
+    pub after_connect: Vec<String>,
 }
 
 impl Default for OracleConnectOptions {
@@ -35,11 +187,42 @@ impl OracleConnectOptions {
     pub fn new() -> Self {
         Self {
             username: "scott".to_owned(),
-            password: "tiger".to_owned(),
+            password: SecretString::from("tiger"),
+            new_password: None,
+            credential_provider: None,
+            access_token: None,
+            token_provider: None,
             connect_string: "//localhost/XE".to_owned(),
+            hosts: Vec::new(),
+            load_balance: false,
             statement_cache_capacity: Self::default_statement_cache_capacity(),
             row_channel_size: Self::default_row_channel_size(),
+            row_chunk_size: Self::default_row_chunk_size(),
             command_channel_size: Self::default_command_channel_size(),
+            connect_timeout: None,
+            connect_retry: ConnectRetry::default(),
+            reconnect: false,
+            close_behavior: CloseBehavior::Rollback,
+            worker_threads: None,
+            worker_stack_size: None,
+            worker_thread_name: Self::default_worker_thread_name(),
+            nls_date_format: None,
+            nls_timestamp_format: None,
+            nls_numeric_characters: None,
+            time_zone: None,
+            nls_language: None,
+            protocol: OracleProtocol::Tcp,
+            ssl_server_dn_match: None,
+            ssl_server_cert_dn: None,
+            wallet_location: None,
+            config_dir: None,
+            edition: None,
+            driver_name: Self::default_driver_name(),
+            module: None,
+            action: None,
+            client_identifier: None,
+            client_info: None,
+            after_connect: Vec::new(),
         }
     }
 
@@ -62,17 +245,49 @@ impl OracleConnectOptions {
         50
     }
 
+    fn default_row_chunk_size() -> usize {
+        32
+    }
+
     fn default_command_channel_size() -> usize {
         50
     }
 
+    fn default_worker_thread_name() -> String {
+        "rbdc-oracle-worker".to_owned()
+    }
+
+    fn default_driver_name() -> String {
+        concat!("rbdc-oracle : ", env!("CARGO_PKG_VERSION")).to_owned()
+    }
+
     pub fn username(mut self, username: impl Into<String>) -> Self {
         self.username = username.into();
         self
     }
 
     pub fn password(mut self, password: impl Into<String>) -> Self {
-        self.password = password.into();
+        self.password = SecretString::new(password);
+        self
+    }
+
+    pub fn new_password(mut self, new_password: impl Into<String>) -> Self {
+        self.new_password = Some(SecretString::new(new_password));
+        self
+    }
+
+    pub fn credential_provider(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.credential_provider = Some(SharedCredentialProvider::new(provider));
+        self
+    }
+
+    pub fn access_token(mut self, token: AccessToken) -> Self {
+        self.access_token = Some(token);
+        self
+    }
+
+    pub fn token_provider(mut self, provider: impl TokenProvider + 'static) -> Self {
+        self.token_provider = Some(SharedTokenProvider::new(provider));
         self
     }
 
@@ -91,10 +306,168 @@ impl OracleConnectOptions {
         self
     }
 
+    /// Values below 1 are treated as 1, sending every row on its own.
+    pub fn row_chunk_size(mut self, size: usize) -> Self {
+        self.row_chunk_size = size;
+        self
+    }
+
     pub fn command_channel_size(mut self, size: usize) -> Self {
         self.command_channel_size = size;
         self
     }
+
+    /// Pins `NLS_DATE_FORMAT`, which nothing sets by default.
+    pub fn nls_date_format(mut self, format: impl Into<String>) -> Self {
+        self.nls_date_format = Some(format.into());
+        self
+    }
+
+    /// Pins `NLS_TIMESTAMP_FORMAT`, which nothing sets by default.
+    pub fn nls_timestamp_format(mut self, format: impl Into<String>) -> Self {
+        self.nls_timestamp_format = Some(format.into());
+        self
+    }
+
+    /// Pins `NLS_NUMERIC_CHARACTERS`, which nothing sets by default.
+    pub fn nls_numeric_characters(mut self, characters: impl Into<String>) -> Self {
+        self.nls_numeric_characters = Some(characters.into());
+        self
+    }
+
+    pub fn time_zone(mut self, time_zone: impl Into<String>) -> Self {
+        self.time_zone = Some(time_zone.into());
+        self
+    }
+
+    pub fn nls_language(mut self, language: impl Into<String>) -> Self {
+        self.nls_language = Some(language.into());
+        self
+    }
+
+    /// Replaces the failover addresses.
+    pub fn hosts(mut self, hosts: Vec<String>) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    /// Appends one failover address, `host` or `host:port`.
+    pub fn add_host(mut self, host: impl Into<String>) -> Self {
+        self.hosts.push(host.into());
+        self
+    }
+
+    pub fn load_balance(mut self, load_balance: bool) -> Self {
+        self.load_balance = load_balance;
+        self
+    }
+
+    /// Stops waiting for a connection attempt after `timeout`; the abandoned
+    /// logon still occupies its thread until the client times out itself.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_retry(mut self, retry: ConnectRetry) -> Self {
+        self.connect_retry = retry;
+        self
+    }
+
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn close_behavior(mut self, close_behavior: CloseBehavior) -> Self {
+        self.close_behavior = close_behavior;
+        self
+    }
+
+    /// Runs connections on `threads` shared threads instead of one thread each.
+    /// Threads blocked in a logon or on a slow consumer are replaced, so more
+    /// may be alive at times.
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.worker_threads = Some(threads);
+        self
+    }
+
+    pub fn worker_stack_size(mut self, stack_size: usize) -> Self {
+        self.worker_stack_size = Some(stack_size);
+        self
+    }
+
+    pub fn worker_thread_name(mut self, prefix: impl Into<String>) -> Self {
+        self.worker_thread_name = prefix.into();
+        self
+    }
+
+    pub fn protocol(mut self, protocol: OracleProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn ssl_server_dn_match(mut self, dn_match: bool) -> Self {
+        self.ssl_server_dn_match = Some(dn_match);
+        self
+    }
+
+    pub fn ssl_server_cert_dn(mut self, cert_dn: impl Into<String>) -> Self {
+        self.ssl_server_cert_dn = Some(cert_dn.into());
+        self
+    }
+
+    pub fn wallet_location(mut self, wallet_location: impl Into<String>) -> Self {
+        self.wallet_location = Some(wallet_location.into());
+        self
+    }
+
+    pub fn config_dir(mut self, config_dir: impl Into<String>) -> Self {
+        self.config_dir = Some(config_dir.into());
+        self
+    }
+
+    pub fn edition(mut self, edition: impl Into<String>) -> Self {
+        self.edition = Some(edition.into());
+        self
+    }
+
+    pub fn driver_name(mut self, driver_name: impl Into<String>) -> Self {
+        self.driver_name = driver_name.into();
+        self
+    }
+
+    pub fn module(mut self, module: impl Into<String>) -> Self {
+        self.module = Some(module.into());
+        self
+    }
+
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.action = Some(action.into());
+        self
+    }
+
+    pub fn client_identifier(mut self, client_identifier: impl Into<String>) -> Self {
+        self.client_identifier = Some(client_identifier.into());
+        self
+    }
+
+    pub fn client_info(mut self, client_info: impl Into<String>) -> Self {
+        self.client_info = Some(client_info.into());
+        self
+    }
+
+    /// Replaces the statements executed on every new connection.
+    pub fn after_connect(mut self, statements: Vec<String>) -> Self {
+        self.after_connect = statements;
+        self
+    }
+
+    /// Appends one statement executed on every new connection.
+    pub fn add_after_connect(mut self, statement: impl Into<String>) -> Self {
+        self.after_connect.push(statement.into());
+        self
+    }
 }
 
 impl ConnectOptions for OracleConnectOptions {
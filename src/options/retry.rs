@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often establishing a connection is attempted when the listener or the
+/// service is temporarily unavailable (ORA-12541, ORA-12514, ...) or the
+/// attempt runs into `connect_timeout`.
+///
+/// The delay before retry `n` is `initial_backoff * 2^(n - 1)`, capped at
+/// `max_backoff`. With `jitter` a random delay between half and all of that is
+/// used instead, so clients restarted together do not retry in lockstep.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ConnectRetry {
+    /// Total number of attempts, including the first one.
+    pub attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+}
+
+impl Default for ConnectRetry {
+    /// A single attempt, no retries.
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+        }
+    }
+}
+
+impl ConnectRetry {
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts,
+            ..Self::default()
+        }
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before the `retry`-th retry (starting at 1), without jitter.
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    pub(crate) fn jittered_delay(&self, retry: u32) -> Duration {
+        let delay = self.delay(retry);
+        if self.jitter {
+            delay.mul_f64(0.5 + fastrand::f64() / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConnectRetry;
+    use std::time::Duration;
+
+    #[test]
+    fn test_exponential_backoff() {
+        let retry = ConnectRetry::new(10)
+            .backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .jitter(false);
+
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(4), Duration::from_millis(800));
+        assert_eq!(retry.delay(5), Duration::from_secs(1));
+        assert_eq!(retry.delay(64), Duration::from_secs(1));
+        assert_eq!(retry.jittered_delay(3), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let retry = ConnectRetry::new(3).backoff(Duration::from_secs(1), Duration::from_secs(1));
+        for _ in 0..100 {
+            let delay = retry.jittered_delay(1);
+            assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::OracleConnectOptions;
+
+impl OracleConnectOptions {
+    /// Statements run on a fresh session: the typed NLS settings first, as a
+    /// single `ALTER SESSION`, followed by the user supplied `after_connect` list.
+    pub(crate) fn session_statements(&self) -> Vec<String> {
+        let mut statements = Vec::with_capacity(self.after_connect.len() + 1);
+        if let Some(alter) = self.alter_session() {
+            statements.push(alter);
+        }
+        statements.extend(self.after_connect.iter().cloned());
+        statements
+    }
+
+    fn alter_session(&self) -> Option<String> {
+        let settings = [
+            ("NLS_LANGUAGE", &self.nls_language),
+            ("NLS_DATE_FORMAT", &self.nls_date_format),
+            ("NLS_TIMESTAMP_FORMAT", &self.nls_timestamp_format),
+            ("NLS_NUMERIC_CHARACTERS", &self.nls_numeric_characters),
+            ("TIME_ZONE", &self.time_zone),
+        ];
+
+        let mut sql = String::from("ALTER SESSION SET");
+        let mut any = false;
+        for (name, value) in settings {
+            if let Some(value) = value {
+                sql.push_str(&format!(" {name} = '{}'", value.replace('\'', "''")));
+                any = true;
+            }
+        }
+
+        any.then_some(sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::OracleConnectOptions;
+
+    #[test]
+    fn test_no_session_settings() {
+        assert!(OracleConnectOptions::new().session_statements().is_empty());
+    }
+
+    #[test]
+    fn test_nls_settings_before_after_connect() {
+        let options = OracleConnectOptions::new()
+            .add_after_connect("ALTER SESSION SET CURRENT_SCHEMA = app")
+            .nls_date_format("YYYY-MM-DD HH24:MI:SS")
+            .nls_numeric_characters(".,")
+            .time_zone("+00:00");
+
+        assert_eq!(
+            options.session_statements(),
+            vec![
+                "ALTER SESSION SET NLS_DATE_FORMAT = 'YYYY-MM-DD HH24:MI:SS' \
+                 NLS_NUMERIC_CHARACTERS = '.,' TIME_ZONE = '+00:00'"
+                    .to_owned(),
+                "ALTER SESSION SET CURRENT_SCHEMA = app".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nls_value_quotes_escaped() {
+        let options = OracleConnectOptions::new().nls_date_format("DD \"of\" 'Month'");
+        assert_eq!(
+            options.session_statements(),
+            vec!["ALTER SESSION SET NLS_DATE_FORMAT = 'DD \"of\" ''Month'''".to_owned()]
+        );
+    }
+}
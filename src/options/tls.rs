@@ -0,0 +1,250 @@
+use crate::OracleConnectOptions;
+use rbdc::Error;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Default listener port for TCPS.
+pub(crate) const TCPS_PORT: u16 = 2484;
+
+/// Network protocol of the listener address.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OracleProtocol {
+    #[default]
+    Tcp,
+    Tcps,
+}
+
+impl OracleProtocol {
+    /// The listener port of an Easy Connect string without one.
+    pub(crate) fn default_port(self) -> u16 {
+        match self {
+            OracleProtocol::Tcp => 1521,
+            OracleProtocol::Tcps => TCPS_PORT,
+        }
+    }
+}
+
+impl FromStr for OracleProtocol {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("tcp") {
+            Ok(Self::Tcp)
+        } else if value.eq_ignore_ascii_case("tcps") {
+            Ok(Self::Tcps)
+        } else {
+            Err(Error::from(format!(
+                "expected tcp or tcps, found `{value}`"
+            )))
+        }
+    }
+}
+
+/// A TCPS descriptor whose `SECURITY` section can still be extended, e.g. with
+/// the token location chosen at connect time.
+#[derive(Debug, Clone)]
+pub(crate) struct TcpsDescriptor {
+    head: String,
+    security: String,
+}
+
+impl TcpsDescriptor {
+    pub(crate) fn to_connect_string(&self, extra_security: &str) -> String {
+        let mut descriptor = self.head.clone();
+        if !self.security.is_empty() || !extra_security.is_empty() {
+            descriptor.push_str(&format!("(SECURITY={}{extra_security})", self.security));
+        }
+        descriptor.push(')');
+        descriptor
+    }
+}
+
+impl OracleConnectOptions {
+    /// The connect string handed to the Oracle client. With TCPS the Easy
+    /// Connect `connect_string` is expanded into a full descriptor carrying the
+    /// `SECURITY` section; otherwise it is used as is.
+    pub(crate) fn effective_connect_string(&self) -> Result<String, Error> {
+        if self.protocol == OracleProtocol::Tcp {
+            if self.ssl_server_dn_match.is_some()
+                || self.ssl_server_cert_dn.is_some()
+                || self.wallet_location.is_some()
+            {
+                return Err(Error::from(
+                    "ssl_server_dn_match, ssl_server_cert_dn and wallet_location require protocol tcps",
+                ));
+            }
+            return Ok(self.connect_string.clone());
+        }
+
+        Ok(self.tcps_descriptor()?.to_connect_string(""))
+    }
+
+    pub(crate) fn tcps_descriptor(&self) -> Result<TcpsDescriptor, Error> {
+        let url = self.easy_connect_url().ok_or_else(|| {
+            Error::from(
+                "protocol tcps requires an Easy Connect connect_string (//host:port/service), \
+                 configure TLS in the descriptor or tnsnames.ora instead",
+            )
+        })?;
+
+        let host = url.host_str().unwrap_or_default();
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+        let port = url.port().unwrap_or(OracleProtocol::Tcps.default_port());
+
+        let mut description = String::new();
+        let mut address = format!("(PROTOCOL=TCPS)(HOST={host})(PORT={port})");
+        let mut connect_data = String::new();
+
+        let mut path = url.path().trim_start_matches('/').splitn(2, '/');
+        let service = path.next().unwrap_or_default();
+        let (service, server) = match service.split_once(':') {
+            Some((service, server)) => (service, Some(server)),
+            None => (service, None),
+        };
+        if !service.is_empty() {
+            connect_data.push_str(&format!("(SERVICE_NAME={service})"));
+        }
+        if let Some(server) = server {
+            connect_data.push_str(&format!("(SERVER={})", server.to_ascii_uppercase()));
+        }
+        if let Some(instance) = path.next().filter(|instance| !instance.is_empty()) {
+            connect_data.push_str(&format!("(INSTANCE_NAME={instance})"));
+        }
+
+        for (key, value) in url.query_pairs() {
+            let value = descriptor_value(&key, &value)?;
+            let parameter = format!("({}={value})", key.to_ascii_uppercase());
+            match &*key {
+                "https_proxy" | "https_proxy_port" => address.push_str(&parameter),
+                "pool_connection_class" | "pool_purity" | "pool_boundary" => {
+                    connect_data.push_str(&parameter)
+                }
+                _ => description.push_str(&parameter),
+            }
+        }
+
+        let mut security = String::new();
+        if let Some(dn_match) = self.ssl_server_dn_match {
+            security.push_str(if dn_match {
+                "(SSL_SERVER_DN_MATCH=ON)"
+            } else {
+                "(SSL_SERVER_DN_MATCH=OFF)"
+            });
+        }
+        if let Some(cert_dn) = &self.ssl_server_cert_dn {
+            let cert_dn = descriptor_value("ssl_server_cert_dn", cert_dn)?;
+            security.push_str(&format!("(SSL_SERVER_CERT_DN=\"{cert_dn}\")"));
+        }
+        if let Some(wallet) = &self.wallet_location {
+            let wallet = descriptor_value("wallet_location", wallet)?;
+            security.push_str(&format!("(MY_WALLET_DIRECTORY=\"{wallet}\")"));
+        }
+
+        let mut head = format!("(DESCRIPTION={description}(ADDRESS={address})");
+        if !connect_data.is_empty() {
+            head.push_str(&format!("(CONNECT_DATA={connect_data})"));
+        }
+        Ok(TcpsDescriptor { head, security })
+    }
+}
+
+/// Descriptor values cannot escape `"`, `(` or `)`; they would end the value
+/// early or add parameters to the descriptor.
+fn descriptor_value<'a>(name: &str, value: &'a str) -> Result<&'a str, Error> {
+    if value.contains(['"', '(', ')']) {
+        return Err(Error::from(format!(
+            "{name} must not contain '\"', '(' or ')', found `{value}`"
+        )));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::OracleConnectOptions;
+    use crate::options::OracleProtocol;
+
+    #[test]
+    fn test_tcp_connect_string_unchanged() {
+        let options = OracleConnectOptions::new().connect_string("//h:1521/svc");
+        assert_eq!(
+            options.effective_connect_string().expect("connect string"),
+            "//h:1521/svc"
+        );
+    }
+
+    #[test]
+    fn test_tcps_descriptor() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//db.example.com:2484/svc")
+            .protocol(OracleProtocol::Tcps)
+            .ssl_server_dn_match(true)
+            .ssl_server_cert_dn("CN=db.example.com,O=Example,C=US")
+            .wallet_location("/etc/oracle/wallet");
+
+        assert_eq!(
+            options.effective_connect_string().expect("connect string"),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=db.example.com)(PORT=2484))\
+             (CONNECT_DATA=(SERVICE_NAME=svc))\
+             (SECURITY=(SSL_SERVER_DN_MATCH=ON)\
+             (SSL_SERVER_CERT_DN=\"CN=db.example.com,O=Example,C=US\")\
+             (MY_WALLET_DIRECTORY=\"/etc/oracle/wallet\")))"
+        );
+    }
+
+    #[test]
+    fn test_tcps_descriptor_with_easy_connect_plus() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//[::1]/svc:pooled/inst1?retry_count=3&pool_purity=self")
+            .protocol(OracleProtocol::Tcps);
+
+        assert_eq!(
+            options.effective_connect_string().expect("connect string"),
+            "(DESCRIPTION=(RETRY_COUNT=3)(ADDRESS=(PROTOCOL=TCPS)(HOST=::1)(PORT=2484))\
+             (CONNECT_DATA=(SERVICE_NAME=svc)(SERVER=POOLED)(INSTANCE_NAME=inst1)(POOL_PURITY=self)))"
+        );
+    }
+
+    #[test]
+    fn test_descriptor_values_cannot_inject_parameters() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//h/svc")
+            .protocol(OracleProtocol::Tcps);
+        for dn in ["CN=db\")(SSL_SERVER_DN_MATCH=OFF", "CN=a (test)"] {
+            let error = options
+                .clone()
+                .ssl_server_cert_dn(dn)
+                .effective_connect_string()
+                .expect_err("rejected");
+            assert!(error.to_string().contains("ssl_server_cert_dn"), "{error}");
+        }
+        let error = options
+            .clone()
+            .wallet_location("/w\")(MY_WALLET_DIRECTORY=\"/tmp")
+            .effective_connect_string()
+            .expect_err("rejected");
+        assert!(error.to_string().contains("wallet_location"), "{error}");
+        let error = options
+            .connect_string("//h/svc?expire_time=1)(SOURCE_ROUTE=ON")
+            .effective_connect_string()
+            .expect_err("rejected");
+        assert!(error.to_string().contains("expire_time"), "{error}");
+    }
+
+    #[test]
+    fn test_tls_options_need_tcps() {
+        let options = OracleConnectOptions::new()
+            .connect_string("//h/svc")
+            .wallet_location("/wallet");
+        assert!(options.effective_connect_string().is_err());
+
+        let options = OracleConnectOptions::new()
+            .connect_string("PROD")
+            .protocol(OracleProtocol::Tcps);
+        assert!(options.effective_connect_string().is_err());
+    }
+}
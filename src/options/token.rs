@@ -0,0 +1,92 @@
+use crate::options::SecretString;
+use futures_core::future::BoxFuture;
+use rbdc::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+/// An OAuth 2.0 access token, or an OCI IAM database token together with the
+/// private key it was issued for.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct AccessToken {
+    pub token: SecretString,
+    #[serde(default)]
+    pub private_key: Option<SecretString>,
+}
+
+impl AccessToken {
+    /// OAuth 2.0 token (`TOKEN_AUTH=OAUTH`).
+    pub fn oauth(token: impl Into<String>) -> Self {
+        Self {
+            token: SecretString::new(token),
+            private_key: None,
+        }
+    }
+
+    /// OCI IAM token with its PEM private key (`TOKEN_AUTH=OCI_TOKEN`).
+    pub fn iam(token: impl Into<String>, private_key: impl Into<String>) -> Self {
+        Self {
+            token: SecretString::new(token),
+            private_key: Some(SecretString::new(private_key)),
+        }
+    }
+
+    pub(crate) fn token_auth(&self) -> &'static str {
+        if self.private_key.is_some() {
+            "OCI_TOKEN"
+        } else {
+            "OAUTH"
+        }
+    }
+}
+
+/// Source of access tokens, awaited before every connection attempt. When the
+/// server rejects a token as invalid or expired, `invalidate` is called and the
+/// attempt is retried once with a fresh token.
+pub trait TokenProvider: Send + Sync {
+    fn access_token(&self) -> BoxFuture<'_, Result<AccessToken, Error>>;
+
+    /// The last token was rejected; the next `access_token` call should not
+    /// return it again.
+    fn invalidate(&self) {}
+}
+
+impl<T: TokenProvider + ?Sized> TokenProvider for Arc<T> {
+    fn access_token(&self) -> BoxFuture<'_, Result<AccessToken, Error>> {
+        (**self).access_token()
+    }
+
+    fn invalidate(&self) {
+        (**self).invalidate()
+    }
+}
+
+/// A [`TokenProvider`] stored in `OracleConnectOptions`. Two values are equal
+/// when they share the same provider.
+#[derive(Clone)]
+pub struct SharedTokenProvider(Arc<dyn TokenProvider>);
+
+impl SharedTokenProvider {
+    pub fn new(provider: impl TokenProvider + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+
+    pub(crate) fn provider(&self) -> &dyn TokenProvider {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for SharedTokenProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenProvider")
+    }
+}
+
+impl PartialEq for SharedTokenProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedTokenProvider {}
@@ -0,0 +1,106 @@
+use crate::OracleConnectOptions;
+use crate::options::OracleProtocol;
+use crate::options::parse::EASY_CONNECT_PARAMETERS;
+use std::fmt::{self, Display, Formatter};
+use url::Url;
+
+const REDACTED_PASSWORD: &str = "***";
+
+impl OracleConnectOptions {
+    /// Canonical `oracle://` (or `oracles://` for TCPS) URL for these options with the password replaced
+    /// by `***`, suitable for logs. This is also what `Display` prints.
+    pub fn to_uri(&self) -> String {
+        self.build_uri(REDACTED_PASSWORD)
+    }
+
+    /// Canonical `oracle://` URL including the real password. Parsing the
+    /// result yields options equal to `self`.
+    pub fn to_uri_with_password(&self) -> String {
+        self.build_uri(self.password.expose_secret())
+    }
+
+    fn build_uri(&self, password: &str) -> String {
+        let mut parameters = self.parameters();
+
+        // Easy Connect strings map onto the authority and path, their Easy
+        // Connect Plus parameters onto the query; aliases and descriptors travel
+        // as query parameters.
+        let url = match self.easy_connect_url() {
+            Some(mut url) => {
+                if url.port().is_none() {
+                    let _ = url.set_port(Some(self.protocol.default_port()));
+                }
+                let _ = url.set_username(&self.username);
+                let _ = url.set_password(Some(password));
+
+                let easy_connect: Vec<(&'static str, String)> = url
+                    .query_pairs()
+                    .filter_map(|(key, value)| {
+                        EASY_CONNECT_PARAMETERS
+                            .iter()
+                            .find(|name| **name == key)
+                            .map(|name| (*name, value.into_owned()))
+                    })
+                    .collect();
+                url.set_query(None);
+                parameters.splice(0..0, easy_connect);
+                Some(url)
+            }
+            None => {
+                let connect_string = if is_tns_alias(&self.connect_string) {
+                    "tns"
+                } else {
+                    "connect_string"
+                };
+                parameters.splice(
+                    0..0,
+                    [
+                        ("username", self.username.clone()),
+                        ("password", password.to_owned()),
+                        (connect_string, self.connect_string.clone()),
+                    ],
+                );
+                None
+            }
+        };
+        let mut url = url.unwrap_or_else(|| Url::parse("oracle:///").expect("static url"));
+        if self.protocol == OracleProtocol::Tcps {
+            let _ = url.set_scheme("oracles");
+        }
+
+        if !parameters.is_empty() {
+            url.query_pairs_mut().extend_pairs(parameters);
+        }
+
+        url.to_string()
+    }
+
+    pub(crate) fn easy_connect_url(&self) -> Option<Url> {
+        if !self.connect_string.starts_with("//") {
+            return None;
+        }
+        Url::parse(&format!("oracle:{}", self.connect_string))
+            .ok()
+            .filter(|url| {
+                url.has_host()
+                    && url.username().is_empty()
+                    && url.fragment().is_none()
+                    && url
+                        .query_pairs()
+                        .all(|(key, _)| EASY_CONNECT_PARAMETERS.contains(&&*key))
+            })
+    }
+}
+
+fn is_tns_alias(connect_string: &str) -> bool {
+    !connect_string.is_empty()
+        && connect_string
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '$' | '#'))
+}
+
+impl Display for OracleConnectOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_uri())
+    }
+}
@@ -21,6 +21,11 @@ impl OracleRow {
         Self { values, columns }
     }
 
+    /// Decodes the value of column `index` as `T`.
+    pub fn try_get<T: Decode>(&self, index: usize) -> Result<T, Error> {
+        self.try_get_raw(index).and_then(T::decode)
+    }
+
     fn try_get_raw(&self, index: usize) -> Result<OracleValueRef<'_>, Error> {
         self.values
             .get(index)
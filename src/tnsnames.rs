@@ -0,0 +1,473 @@
+//! A `tnsnames.ora` reader.
+//!
+//! Entries are parsed into a tree of `(NAME=value)` nodes, which is enough to
+//! inspect descriptors and turn aliases back into connect strings without the
+//! Oracle client. `IFILE` includes are expanded in place, relative to the file
+//! that names them.
+
+use rbdc::Error;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "tnsnames.ora";
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Parsed contents of a `tnsnames.ora` file and its includes.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TnsNames {
+    entries: Vec<TnsEntry>,
+}
+
+/// One `alias[, alias...] = (DESCRIPTION=...)` definition.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TnsEntry {
+    pub aliases: Vec<String>,
+    pub descriptor: Vec<TnsNode>,
+}
+
+/// A `(NAME=value)` pair of a descriptor.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TnsNode {
+    pub name: String,
+    pub value: TnsValue,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TnsValue {
+    Text(String),
+    List(Vec<TnsNode>),
+}
+
+impl TnsNames {
+    /// Parses file contents. `IFILE` entries are rejected because there is no
+    /// file to resolve them against; use [`TnsNames::from_file`] for those.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let mut names = Self::default();
+        for item in Parser::new(source).items()? {
+            match item {
+                Item::Entry(entry) => names.entries.push(entry),
+                Item::Include(path) => {
+                    return Err(Error::from(format!(
+                        "IFILE={path} needs a file location, use TnsNames::from_file"
+                    )));
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Reads a `tnsnames.ora` file, following `IFILE` includes.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut names = Self::default();
+        let mut seen = HashSet::new();
+        names.load(path.as_ref(), &mut seen, 0)?;
+        Ok(names)
+    }
+
+    /// Reads `tnsnames.ora` from `config_dir`, or when `None` from `$TNS_ADMIN`
+    /// and then `$ORACLE_HOME/network/admin`.
+    pub fn from_config_dir(config_dir: Option<&Path>) -> Result<Self, Error> {
+        let dir = match config_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => default_config_dir()
+                .ok_or_else(|| Error::from("Neither TNS_ADMIN nor ORACLE_HOME is set"))?,
+        };
+        Self::from_file(dir.join(FILE_NAME))
+    }
+
+    pub fn entries(&self) -> &[TnsEntry] {
+        &self.entries
+    }
+
+    /// Looks an alias up, ignoring case. The first definition wins.
+    pub fn get(&self, alias: &str) -> Option<&TnsEntry> {
+        self.entries.iter().find(|entry| {
+            entry
+                .aliases
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(alias))
+        })
+    }
+
+    /// Resolves an alias to a connect string usable as
+    /// `OracleConnectOptions::connect_string`.
+    pub fn resolve(&self, alias: &str) -> Option<String> {
+        self.get(alias).map(TnsEntry::connect_string)
+    }
+
+    fn load(
+        &mut self,
+        path: &Path,
+        seen: &mut HashSet<PathBuf>,
+        depth: usize,
+    ) -> Result<(), Error> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(Error::from(format!(
+                "{}: IFILE nesting deeper than {MAX_INCLUDE_DEPTH}",
+                path.display()
+            )));
+        }
+
+        let canonical =
+            fs::canonicalize(path).map_err(|e| Error::from(format!("{}: {e}", path.display())))?;
+        if !seen.insert(canonical.clone()) {
+            return Err(Error::from(format!(
+                "{}: IFILE includes itself",
+                path.display()
+            )));
+        }
+
+        let source = fs::read_to_string(&canonical)
+            .map_err(|e| Error::from(format!("{}: {e}", path.display())))?;
+        let items = Parser::new(&source)
+            .items()
+            .map_err(|e| Error::from(format!("{}: {e}", path.display())))?;
+
+        for item in items {
+            match item {
+                Item::Entry(entry) => self.entries.push(entry),
+                Item::Include(include) => {
+                    let include = canonical
+                        .parent()
+                        .map(|dir| dir.join(&include))
+                        .unwrap_or_else(|| PathBuf::from(&include));
+                    self.load(&include, seen, depth + 1)?;
+                }
+            }
+        }
+
+        seen.remove(&canonical);
+        Ok(())
+    }
+}
+
+impl TnsEntry {
+    /// The descriptor in its compact form, e.g. `(DESCRIPTION=(ADDRESS=...))`.
+    pub fn connect_string(&self) -> String {
+        let mut out = String::new();
+        for node in &self.descriptor {
+            out.push_str(&node.to_string());
+        }
+        out
+    }
+
+    /// The first node called `name` anywhere in the descriptor, ignoring case.
+    pub fn find(&self, name: &str) -> Option<&TnsNode> {
+        self.descriptor.iter().find_map(|node| node.find(name))
+    }
+}
+
+impl TnsNode {
+    /// This node or the first descendant called `name`, ignoring case.
+    pub fn find(&self, name: &str) -> Option<&TnsNode> {
+        if self.name.eq_ignore_ascii_case(name) {
+            return Some(self);
+        }
+        match &self.value {
+            TnsValue::Text(_) => None,
+            TnsValue::List(children) => children.iter().find_map(|child| child.find(name)),
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        match &self.value {
+            TnsValue::Text(text) => Some(text),
+            TnsValue::List(_) => None,
+        }
+    }
+
+    pub fn children(&self) -> &[TnsNode] {
+        match &self.value {
+            TnsValue::Text(_) => &[],
+            TnsValue::List(children) => children,
+        }
+    }
+}
+
+impl Display for TnsNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "({}=", self.name)?;
+        match &self.value {
+            TnsValue::Text(text) => f.write_str(text)?,
+            TnsValue::List(children) => {
+                for child in children {
+                    child.fmt(f)?;
+                }
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+fn default_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("TNS_ADMIN") {
+        return Some(PathBuf::from(dir));
+    }
+    std::env::var_os("ORACLE_HOME").map(|home| PathBuf::from(home).join("network").join("admin"))
+}
+
+enum Item {
+    Entry(TnsEntry),
+    Include(String),
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn items(mut self) -> Result<Vec<Item>, Error> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                return Ok(items);
+            }
+
+            let names = self.take_until(|c| c == '=' || c == '(' || c == ')');
+            self.expect('=')?;
+            let aliases: Vec<String> = names
+                .split(',')
+                .map(|name| name.trim().to_owned())
+                .collect();
+            if aliases.iter().any(String::is_empty) {
+                return Err(self.error("empty alias"));
+            }
+
+            self.skip_whitespace();
+            if aliases.len() == 1 && aliases[0].eq_ignore_ascii_case("IFILE") {
+                let path = self.take_until(|c| c.is_whitespace() || c == '#');
+                items.push(Item::Include(unquote(path.trim()).to_owned()));
+                continue;
+            }
+
+            if self.peek() != Some('(') {
+                return Err(self.error(&format!("expected `(` after `{}=`", aliases[0])));
+            }
+            let descriptor = self.nodes()?;
+            items.push(Item::Entry(TnsEntry {
+                aliases,
+                descriptor,
+            }));
+        }
+    }
+
+    fn nodes(&mut self) -> Result<Vec<TnsNode>, Error> {
+        let mut nodes = Vec::new();
+        while self.peek() == Some('(') {
+            nodes.push(self.node()?);
+            self.skip_whitespace();
+        }
+        Ok(nodes)
+    }
+
+    fn node(&mut self) -> Result<TnsNode, Error> {
+        self.expect('(')?;
+        self.skip_whitespace();
+        let name = self.take_until(|c| c == '=' || c == '(' || c == ')');
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err(self.error("empty parameter name"));
+        }
+        self.expect('=')?;
+        self.skip_whitespace();
+
+        let value = if self.peek() == Some('(') {
+            TnsValue::List(self.nodes()?)
+        } else {
+            TnsValue::Text(self.text()?)
+        };
+
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(TnsNode { name, value })
+    }
+
+    fn text(&mut self) -> Result<String, Error> {
+        let start = self.pos;
+        let mut quoted = false;
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => quoted = !quoted,
+                '(' | ')' if !quoted => break,
+                '\n' => self.line += 1,
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+        if quoted {
+            return Err(self.error("unterminated quoted value"));
+        }
+        Ok(self.source[start..self.pos].trim().to_owned())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                self.take_until(|c| c == '\n');
+            } else if c.is_whitespace() {
+                if c == '\n' {
+                    self.line += 1;
+                }
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn take_until(&mut self, stop: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if stop(c) {
+                break;
+            }
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.source[start..self.pos]
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected `{expected}`, found `{c}`"))),
+            None => Err(self.error(&format!("expected `{expected}`, found end of file"))),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::from(format!("line {}: {message}", self.line))
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TnsNames, TnsValue};
+    use std::fs;
+
+    const SAMPLE: &str = r#"
+# production
+PROD, PROD.WORLD =
+  (DESCRIPTION =
+    (ADDRESS_LIST =
+      (ADDRESS = (PROTOCOL = TCP)(HOST = db1.example.com)(PORT = 1521))
+      (ADDRESS = (PROTOCOL = TCP)(HOST = db2.example.com)(PORT = 1521)) # standby
+    )
+    (CONNECT_DATA =
+      (SERVICE_NAME = prod.example.com)
+    )
+  )
+
+secure=(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=h)(PORT=2484))
+  (SECURITY=(SSL_SERVER_CERT_DN="CN=h,O=Example (Test),C=US")))
+"#;
+
+    #[test]
+    fn test_parse() {
+        let names = TnsNames::parse(SAMPLE).expect("tnsnames");
+        assert_eq!(names.entries().len(), 2);
+
+        let prod = names.get("prod.world").expect("alias");
+        assert_eq!(prod.aliases, vec!["PROD", "PROD.WORLD"]);
+        let addresses = prod.find("ADDRESS_LIST").expect("address list");
+        assert_eq!(addresses.children().len(), 2);
+        assert_eq!(
+            prod.find("SERVICE_NAME").and_then(|node| node.text()),
+            Some("prod.example.com")
+        );
+        assert!(matches!(
+            prod.descriptor[0].value,
+            TnsValue::List(ref children) if children.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let names = TnsNames::parse(SAMPLE).expect("tnsnames");
+        assert_eq!(
+            names.resolve("PROD").as_deref(),
+            Some(
+                "(DESCRIPTION=(ADDRESS_LIST=(ADDRESS=(PROTOCOL=TCP)(HOST=db1.example.com)(PORT=1521))\
+                 (ADDRESS=(PROTOCOL=TCP)(HOST=db2.example.com)(PORT=1521)))\
+                 (CONNECT_DATA=(SERVICE_NAME=prod.example.com)))"
+            )
+        );
+        assert_eq!(
+            names.resolve("SECURE").as_deref(),
+            Some(
+                "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCPS)(HOST=h)(PORT=2484))\
+                 (SECURITY=(SSL_SERVER_CERT_DN=\"CN=h,O=Example (Test),C=US\")))"
+            )
+        );
+        assert_eq!(names.resolve("missing"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = TnsNames::parse("A = (DESCRIPTION = (ADDRESS = (HOST = h)\n").expect_err("eof");
+        assert!(err.to_string().contains("line 2"));
+        assert!(TnsNames::parse("A = B").is_err());
+        assert!(TnsNames::parse("IFILE = other.ora").is_err());
+    }
+
+    #[test]
+    fn test_ifile() {
+        let dir = std::env::temp_dir().join(format!("rbdc-oracle-tns-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).expect("dir");
+        fs::write(
+            dir.join("tnsnames.ora"),
+            "MAIN = (DESCRIPTION = (ADDRESS = (HOST = a)))\nIFILE = sub/extra.ora\n",
+        )
+        .expect("write");
+        fs::write(
+            dir.join("sub").join("extra.ora"),
+            "EXTRA = (DESCRIPTION = (ADDRESS = (HOST = b)))\n",
+        )
+        .expect("write");
+
+        let names = TnsNames::from_config_dir(Some(&dir)).expect("tnsnames");
+        assert_eq!(
+            names.resolve("extra").as_deref(),
+            Some("(DESCRIPTION=(ADDRESS=(HOST=b)))")
+        );
+        assert!(names.get("MAIN").is_some());
+
+        fs::write(
+            dir.join("sub").join("extra.ora"),
+            "IFILE = ../tnsnames.ora\n",
+        )
+        .expect("write");
+        assert!(TnsNames::from_config_dir(Some(&dir)).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
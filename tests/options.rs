@@ -25,6 +25,7 @@ fn test_default_builder() {
     assert_eq!(options.connect_string, "//db/service");
     assert_eq!(options.statement_cache_capacity, 100);
     assert_eq!(options.row_channel_size, 50);
+    assert_eq!(options.row_chunk_size, 32);
     assert_eq!(options.command_channel_size, 50);
 }
 
@@ -39,3 +40,76 @@ fn test_with_credentials() {
     assert_eq!(options.row_channel_size, 50);
     assert_eq!(options.command_channel_size, 50);
 }
+
+#[test]
+fn test_after_connect() {
+    let options = OracleConnectOptions::new()
+        .after_connect(vec![
+            "ALTER SESSION SET NLS_DATE_FORMAT = 'YYYY-MM-DD'".to_owned(),
+        ])
+        .add_after_connect("ALTER SESSION SET CURRENT_SCHEMA = app");
+
+    assert_eq!(
+        options.after_connect,
+        vec![
+            "ALTER SESSION SET NLS_DATE_FORMAT = 'YYYY-MM-DD'".to_owned(),
+            "ALTER SESSION SET CURRENT_SCHEMA = app".to_owned(),
+        ]
+    );
+    assert!(OracleConnectOptions::default().after_connect.is_empty());
+}
+
+#[test]
+fn test_tracing_attributes() {
+    let options = OracleConnectOptions::new()
+        .module("billing")
+        .action("nightly")
+        .client_identifier("svc-billing")
+        .client_info("host-1");
+
+    assert!(options.driver_name.starts_with("rbdc-oracle : "));
+    assert_eq!(options.module.as_deref(), Some("billing"));
+    assert_eq!(options.action.as_deref(), Some("nightly"));
+    assert_eq!(options.client_identifier.as_deref(), Some("svc-billing"));
+    assert_eq!(options.client_info.as_deref(), Some("host-1"));
+}
+
+#[test]
+fn test_edition() {
+    let options = OracleConnectOptions::new().edition("RELEASE_2");
+    assert_eq!(options.edition.as_deref(), Some("RELEASE_2"));
+    assert_eq!(OracleConnectOptions::default().edition, None);
+}
+
+#[test]
+fn test_config_dir() {
+    let options: OracleConnectOptions =
+        "oracle:///?tns=PROD&username=u&password=p&config_dir=%2Fetc%2Foracle"
+            .parse()
+            .expect("oracle uri");
+    assert_eq!(options.config_dir.as_deref(), Some("/etc/oracle"));
+    assert_eq!(options.connect_string, "PROD");
+}
+
+#[test]
+fn test_debug_redacts_password() {
+    let options = OracleConnectOptions::with_credentials("user", "s3cret", "//db/service");
+    let debug = format!("{options:?}");
+
+    assert!(debug.contains("password: ***"));
+    assert!(!debug.contains("s3cret"));
+    assert_eq!(options.password.expose_secret(), "s3cret");
+}
+
+#[test]
+fn test_new_password() {
+    let options =
+        OracleConnectOptions::with_credentials("user", "old", "//db/service").new_password("n3w");
+
+    assert_eq!(
+        options.new_password.as_ref().map(|p| p.expose_secret()),
+        Some("n3w")
+    );
+    assert!(!format!("{options:?}").contains("n3w"));
+    assert!(!options.to_uri_with_password().contains("n3w"));
+}